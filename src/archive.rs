@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+
+/// The separator between archive path and entry path, such as `archive.zip!/src/lib.rs`.
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+
+    Tar,
+
+    TarGz,
+
+    TarXz,
+}
+
+impl ArchiveKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        if name.ends_with(".zip") || name.ends_with(".jar") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else {
+            None
+        }
+    }
+}
+
+/// List the path of file entries in the archive, the directory entries are skipped.
+pub fn list_entries(path: &Path, kind: ArchiveKind) -> std::io::Result<Vec<String>> {
+    let file = BufReader::new(File::open(path)?);
+
+    match kind {
+        ArchiveKind::Zip => {
+            let archive = zip::ZipArchive::new(file).map_err(std::io::Error::other)?;
+
+            Ok(archive
+                .file_names()
+                .filter(|v| !v.ends_with('/'))
+                .map(String::from)
+                .collect())
+        }
+        ArchiveKind::Tar => list_tar_entries(file),
+        ArchiveKind::TarGz => list_tar_entries(flate2::read::GzDecoder::new(file)),
+        ArchiveKind::TarXz => list_tar_entries(xz2::read::XzDecoder::new(file)),
+    }
+}
+
+fn list_tar_entries(reader: impl Read) -> std::io::Result<Vec<String>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut ret = vec![];
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+
+        if entry.header().entry_type().is_file() {
+            ret.push(entry.path()?.to_string_lossy().into_owned());
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn archive_kind_from_name() {
+        assert_eq!(ArchiveKind::from_name("a.JAR"), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::from_name("a.tgz"), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::from_name("a.tar.xz"), Some(ArchiveKind::TarXz));
+        assert_eq!(ArchiveKind::from_name("a.gz"), None);
+    }
+
+    #[test]
+    fn list_archive_entries() {
+        let dir = std::env::temp_dir().join(format!("findsource-archive-{}", std::process::id()));
+        let zip_path = dir.join("a.zip");
+        let tar_path = dir.join("a.tar.gz");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+
+        writer.add_directory("src/", options).unwrap();
+        writer.start_file("src/lib.rs", options).unwrap();
+        writer.write_all(b"fn a() {}").unwrap();
+        writer.finish().unwrap();
+
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&tar_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+
+        header.set_size(3);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "src/main.c", &b"int"[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let zip_entries = list_entries(&zip_path, ArchiveKind::Zip);
+        let tar_entries = list_entries(&tar_path, ArchiveKind::TarGz);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(zip_entries.unwrap(), ["src/lib.rs"]);
+        assert_eq!(tar_entries.unwrap(), ["src/main.c"]);
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::mpsc::channel;
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::cancel::CancelToken;
+use crate::error::FindError;
+use crate::finder::Filter;
+use crate::finder::Finder;
+use crate::finder::MatchStream;
+use crate::finder::Matched;
+use crate::git::GitSelect;
+use crate::matcher::Matcher;
+use crate::walk::Walk;
+
+/// Category id of the extension added by [`FinderBuilder::add_extension`].
+pub const EXTENSION_CATEGORY: &str = "ext";
+
+/// Category id of the whole name added by [`FinderBuilder::add_whole`].
+pub const WHOLE_CATEGORY: &str = "whole";
+
+/// Build a [`Finder`] without the command line parser.
+///
+/// ```no_run
+/// # async fn search() {
+/// use findsource::FinderBuilder;
+/// use tokio_stream::StreamExt;
+///
+/// let mut builder = FinderBuilder::new();
+///
+/// builder.add_root("src").add_category("rust", ["rs"]);
+///
+/// let mut matches = builder.find();
+///
+/// while let Some(matched) = matches.next().await {
+///     match matched {
+///         Ok(matched) => println!("{}", matched.path),
+///         Err(e) => eprintln!("{e}"),
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FinderBuilder {
+    roots: Vec<PathBuf>,
+
+    whos: Vec<(String, String)>,
+
+    exts: Vec<(String, String)>,
+
+    ex_whos: Vec<String>,
+
+    ex_exts: Vec<String>,
+
+    hidden: bool,
+
+    recursive: bool,
+
+    igcase: bool,
+
+    invert: bool,
+
+    full: bool,
+
+    #[cfg(feature = "archive")]
+    archive: bool,
+
+    #[cfg(feature = "index")]
+    index: bool,
+
+    #[cfg(feature = "daemon")]
+    daemon: bool,
+
+    git: Vec<GitSelect>,
+
+    debug: bool,
+
+    verbose: bool,
+
+    capacity: usize,
+
+    threads: usize,
+
+    matchers: Vec<Arc<dyn Matcher>>,
+
+    token: CancelToken,
+}
+
+impl Default for FinderBuilder {
+    fn default() -> Self {
+        Self {
+            roots: vec![],
+            whos: vec![],
+            exts: vec![],
+            ex_whos: vec![],
+            ex_exts: vec![],
+            hidden: false,
+            recursive: true,
+            igcase: false,
+            invert: false,
+            full: false,
+            #[cfg(feature = "archive")]
+            archive: false,
+            #[cfg(feature = "index")]
+            index: false,
+            #[cfg(feature = "daemon")]
+            daemon: false,
+            git: vec![],
+            debug: false,
+            verbose: false,
+            capacity: 512,
+            threads: std::thread::available_parallelism()
+                .map(|v| v.get())
+                .unwrap_or(4),
+            matchers: vec![],
+            token: CancelToken::default(),
+        }
+    }
+}
+
+impl FinderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_root(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.roots.push(path.into());
+        self
+    }
+
+    /// Match the file extension, such as `rs`, the category of it is `ext`.
+    pub fn add_extension(&mut self, ext: impl Into<String>) -> &mut Self {
+        self.exts.push((ext.into(), EXTENSION_CATEGORY.to_owned()));
+        self
+    }
+
+    /// Match the whole file name, such as `Makefile`, the category of it is `whole`.
+    pub fn add_whole(&mut self, name: impl Into<String>) -> &mut Self {
+        self.whos.push((name.into(), WHOLE_CATEGORY.to_owned()));
+        self
+    }
+
+    /// Match the file extensions with given category id.
+    pub fn add_category<I, S>(&mut self, id: impl Into<String>, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let id = id.into();
+
+        self.exts
+            .extend(exts.into_iter().map(|ext| (ext.into(), id.clone())));
+        self
+    }
+
+    /// Match the whole file names with given category id.
+    pub fn add_whole_category<I, S>(&mut self, id: impl Into<String>, names: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let id = id.into();
+
+        self.whos
+            .extend(names.into_iter().map(|name| (name.into(), id.clone())));
+        self
+    }
+
+    /// Add a custom matcher, it is checked after the whole names and extensions.
+    ///
+    /// The daemon is not used if there is any custom matcher.
+    pub fn add_matcher(&mut self, matcher: impl Matcher + 'static) -> &mut Self {
+        self.matchers.push(Arc::new(matcher));
+        self
+    }
+
+    pub fn exclude_extension(&mut self, ext: impl Into<String>) -> &mut Self {
+        self.ex_exts.push(ext.into());
+        self
+    }
+
+    pub fn exclude_whole(&mut self, name: impl Into<String>) -> &mut Self {
+        self.ex_whos.push(name.into());
+        self
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) -> &mut Self {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn set_recursive(&mut self, recursive: bool) -> &mut Self {
+        self.recursive = recursive;
+        self
+    }
+
+    pub fn set_ignore_case(&mut self, igcase: bool) -> &mut Self {
+        self.igcase = igcase;
+        self
+    }
+
+    pub fn set_invert(&mut self, invert: bool) -> &mut Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Report the absolute path of matched file.
+    pub fn set_full(&mut self, full: bool) -> &mut Self {
+        self.full = full;
+        self
+    }
+
+    /// Match the entries inside zip and tar archives.
+    #[cfg(feature = "archive")]
+    pub fn set_archive(&mut self, archive: bool) -> &mut Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Search the files recorded in the persistent index of roots.
+    #[cfg(feature = "index")]
+    pub fn set_index(&mut self, index: bool) -> &mut Self {
+        self.index = index;
+        self
+    }
+
+    /// Query the daemon for the roots it serves, it is only available on unix.
+    #[cfg(feature = "daemon")]
+    pub fn set_daemon(&mut self, daemon: bool) -> &mut Self {
+        self.daemon = daemon;
+        self
+    }
+
+    pub fn add_git(&mut self, select: GitSelect) -> &mut Self {
+        self.git.push(select);
+        self
+    }
+
+    pub fn set_debug(&mut self, debug: bool) -> &mut Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) -> &mut Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Set the capacity of channel used by [`find`](Self::find) and [`walk`](Self::walk).
+    pub fn set_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set the count of threads used by [`walk`](Self::walk).
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Set the token used to stop the searching, the searching started by this builder share the same token.
+    pub fn set_cancel_token(&mut self, token: CancelToken) -> &mut Self {
+        self.token = token;
+        self
+    }
+
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.token
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Build the matching rules, the category added first wins if they share an extension or whole name.
+    pub fn build_filter(&self) -> Filter {
+        let mut whos = HashMap::<String, String>::default();
+        let mut exts = HashMap::<String, String>::default();
+        let case = |v: &String| {
+            if self.igcase {
+                v.to_lowercase()
+            } else {
+                v.clone()
+            }
+        };
+
+        for (name, id) in self.whos.iter() {
+            whos.entry(case(name)).or_insert_with(|| id.clone());
+        }
+        for (ext, id) in self.exts.iter() {
+            exts.entry(case(ext)).or_insert_with(|| id.clone());
+        }
+        for name in self.ex_whos.iter() {
+            whos.remove(&case(name));
+        }
+        for ext in self.ex_exts.iter() {
+            exts.remove(&case(ext));
+        }
+        let mut filter = Filter::new(self.hidden, self.igcase, self.invert, whos, exts);
+
+        for matcher in self.matchers.iter() {
+            filter.add_matcher(Arc::clone(matcher));
+        }
+        filter
+    }
+
+    /// Build the finder, the matched files will be sent to `sender`.
+    pub fn build(&self, sender: Sender<Result<Matched, FindError>>) -> Finder {
+        Finder {
+            full: self.full,
+            debug: self.debug,
+            verb: self.verbose,
+            reverse: self.recursive,
+            #[cfg(feature = "archive")]
+            archive: self.archive,
+            #[cfg(feature = "index")]
+            index: self.index,
+            #[cfg(feature = "daemon")]
+            daemon: self.daemon,
+            git: self.git.clone(),
+            filter: Arc::new(self.build_filter()),
+            sender,
+            token: self.token.clone(),
+        }
+    }
+
+    /// Start searching the roots, return a stream of matched files.
+    ///
+    /// It must be called within a tokio runtime.
+    pub fn find(&self) -> MatchStream {
+        let (tx, rx) = channel(self.capacity);
+
+        self.build(tx).search(self.roots.clone());
+        ReceiverStream::new(rx)
+    }
+
+    /// Start searching the roots on a runtime of new threads, return a blocking iterator of matched files.
+    ///
+    /// It does not need a tokio runtime, and must not be called within an asynchronous context.
+    ///
+    /// ```no_run
+    /// use findsource::FinderBuilder;
+    ///
+    /// let mut builder = FinderBuilder::new();
+    ///
+    /// builder.add_root("proto").add_extension("proto");
+    ///
+    /// for matched in builder.walk() {
+    ///     match matched {
+    ///         Ok(matched) => println!("cargo:rerun-if-changed={}", matched.path),
+    ///         Err(e) => eprintln!("{e}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn walk(&self) -> Walk {
+        Walk::spawn(self.clone(), self.threads, self.capacity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::matcher::Candidate;
+
+    fn category(filter: &Filter, name: &str) -> Option<String> {
+        filter
+            .category(&Candidate::new(std::path::Path::new(name), name))
+            .map(String::from)
+    }
+
+    #[test]
+    fn first_category_wins() {
+        let mut builder = FinderBuilder::new();
+
+        builder
+            .add_category("c", ["h", "c"])
+            .add_category("cpp", ["h", "cpp"])
+            .add_whole_category("make", ["Makefile"])
+            .add_extension("txt")
+            .exclude_extension("c");
+        let filter = builder.build_filter();
+
+        assert_eq!(category(&filter, "a.h").as_deref(), Some("c"));
+        assert_eq!(category(&filter, "a.cpp").as_deref(), Some("cpp"));
+        assert_eq!(
+            category(&filter, "a.txt").as_deref(),
+            Some(EXTENSION_CATEGORY)
+        );
+        assert_eq!(category(&filter, "Makefile").as_deref(), Some("make"));
+        assert_eq!(category(&filter, "a.c"), None);
+    }
+
+    #[test]
+    fn ignore_case_filter() {
+        let mut builder = FinderBuilder::new();
+
+        builder.add_category("rust", ["RS"]).set_ignore_case(true);
+        let filter = builder.build_filter();
+
+        assert_eq!(category(&filter, "lib.rs").as_deref(), Some("rust"));
+        assert_eq!(category(&filter, "LIB.Rs").as_deref(), Some("rust"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Get the path of file relative to the search root which contains it,
+/// the nearest root is used if there are multiple roots contain the file.
+/// The name of root is kept if there are multiple roots, so the files of different roots are not mixed.
+pub fn relative_path(path: &Path, roots: &[PathBuf]) -> PathBuf {
+    roots
+        .iter()
+        .flat_map(|root| [Some(root.clone()), dunce::canonicalize(root).ok()])
+        .flatten()
+        .filter_map(|root| {
+            let base = if root.is_file() {
+                root.parent().map(Path::to_path_buf).unwrap_or_default()
+            } else {
+                root
+            };
+
+            path.strip_prefix(&base)
+                .ok()
+                .map(|rel| (base.components().count(), rel.to_path_buf(), base))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .filter(|(_, rel, _)| !rel.as_os_str().is_empty())
+        .map(|(_, rel, base)| {
+            if roots.len() > 1 {
+                root_name(&base).join(rel)
+            } else {
+                rel
+            }
+        })
+        .unwrap_or_else(|| path.file_name().map(PathBuf::from).unwrap_or_default())
+}
+
+/// The last component of root, such as `src` of `../src` or the current directory name of `.`.
+fn root_name(root: &Path) -> PathBuf {
+    match root.file_name() {
+        Some(name) => PathBuf::from(name),
+        None => dunce::canonicalize(root)
+            .ok()
+            .and_then(|v| v.file_name().map(PathBuf::from))
+            .unwrap_or_default(),
+    }
+}
+
+/// Sort the files and remove the file found twice,
+/// return error if different files have the same relative path.
+pub fn check_collision(files: &mut Vec<(PathBuf, PathBuf)>) -> std::io::Result<()> {
+    files.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+    files.dedup();
+    for pair in files.windows(2) {
+        if pair[0].1 == pair[1].1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "Both {:?} and {:?} are bundled as {:?}",
+                    pair[0].0, pair[1].0, pair[0].1
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Copy the files into `dir`, keep the relative tree under the search root.
+pub async fn copy_to(dir: &Path, files: &[(PathBuf, PathBuf)]) -> std::io::Result<()> {
+    for (path, rel) in files {
+        let dest = dir.join(rel);
+
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::copy(path, &dest).await?;
+    }
+    Ok(())
+}
+
+/// Write the files into a tar archive, compress it with gzip if the name ends with `.gz` or `.tgz`.
+pub fn write_tar(out: &Path, files: &[(PathBuf, PathBuf)]) -> std::io::Result<()> {
+    let file = BufWriter::new(File::create(out)?);
+    let name = out.to_string_lossy().to_lowercase();
+
+    if name.ends_with(".gz") || name.ends_with(".tgz") {
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        for (path, rel) in files {
+            builder.append_path_with_name(path, rel)?;
+        }
+        builder.into_inner()?.finish()?;
+    } else {
+        let mut builder = tar::Builder::new(file);
+
+        for (path, rel) in files {
+            builder.append_path_with_name(path, rel)?;
+        }
+        builder.finish()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn relative_to_nearest_root() {
+        let roots = [PathBuf::from("/a"), PathBuf::from("/a/b")];
+
+        assert_eq!(
+            relative_path(Path::new("/a/b/c.rs"), &roots[..1]),
+            Path::new("b/c.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/b/c.rs"), &roots),
+            Path::new("b/c.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/d.rs"), &roots),
+            Path::new("a/d.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/e/f.rs"), &roots),
+            Path::new("f.rs")
+        );
+    }
+
+    #[test]
+    fn bundle_collision() {
+        let mut files = vec![
+            (PathBuf::from("x/a.rs"), PathBuf::from("a.rs")),
+            (PathBuf::from("x/a.rs"), PathBuf::from("a.rs")),
+        ];
+
+        assert!(check_collision(&mut files).is_ok());
+        assert_eq!(files.len(), 1);
+        files.push((PathBuf::from("y/a.rs"), PathBuf::from("a.rs")));
+        assert!(check_collision(&mut files).is_err());
+    }
+
+    #[test]
+    fn tar_bundle() {
+        let dir = std::env::temp_dir().join(format!("findsource-bundle-{}", std::process::id()));
+        let src = dir.join("a.rs");
+        let out = dir.join("out.tar.gz");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&src, "fn a() {}").unwrap();
+        write_tar(&out, &[(src, PathBuf::from("src/a.rs"))]).unwrap();
+
+        let file = File::open(&out).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let names: Vec<_> = archive
+            .entries()
+            .unwrap()
+            .map(|v| v.unwrap().path().unwrap().into_owned())
+            .collect();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, [PathBuf::from("src/a.rs")]);
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// Stop the searching cleanly, the clones share the same state.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::FinderBuilder;
+
+    #[test]
+    fn share_cancel_state() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn stop_cancelled_search() {
+        let token = CancelToken::new();
+        let mut builder = FinderBuilder::new();
+
+        builder
+            .add_root(env!("CARGO_MANIFEST_DIR"))
+            .add_extension("rs")
+            .set_cancel_token(token.clone());
+        token.cancel();
+        let mut matches = builder.find();
+
+        assert!(matches.next().await.is_none());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use aopt::prelude::AFwdParser;
+use findsource::FindError;
+
+use crate::json::JsonConfig;
+use crate::json::JsonOptCollection;
+
+/// The names of options added by `Cli::new` before loading the configurations.
+pub const BUILTIN_NAMES: [&str; 12] = [
+    "-d",
+    "--debug",
+    "-?",
+    "--help",
+    "-v",
+    "--verbose",
+    "-l",
+    "--load",
+    "-p",
+    "--profile",
+    "--/defaults",
+    "--/project-config",
+];
+
+/// The ids of default configuration which hold whole filenames, not extensions.
+const WHOLE_IDS: [&str; 2] = ["whole", "Whole"];
+
+/// The problem found in configuration.
+#[derive(Debug)]
+pub struct Problem {
+    pub error: bool,
+
+    pub path: PathBuf,
+
+    pub message: String,
+}
+
+impl Problem {
+    /// The configuration can not be found or parsed.
+    pub fn load_error(name: &str, error: FindError) -> Self {
+        let path = error
+            .path()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(name));
+        let message = match error {
+            FindError::InvalidConfig { message, .. } => message,
+            error => error.to_string(),
+        };
+
+        Self {
+            error: true,
+            path,
+            message,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = if self.error { "ERROR" } else { "WARN" };
+
+        write!(f, "{}: {:?}: {}", level, self.path, self.message)
+    }
+}
+
+/// Check the configurations in merging order, the default configuration is checked with them.
+/// The chain is the configurations loaded together, such as `-l c -l cpp`.
+pub fn check(default: &JsonOptCollection, chain: &[(PathBuf, JsonOptCollection)]) -> Vec<Problem> {
+    let default_path = PathBuf::from("<default>");
+    let mut problems = vec![];
+    let mut options = BTreeMap::<&str, (&PathBuf, &str)>::new();
+    let mut names = BTreeMap::<&str, &str>::new();
+    let mut categories = BTreeMap::<&str, BTreeSet<&str>>::new();
+    let mut report = |error: bool, path: &PathBuf, message: String| {
+        problems.push(Problem {
+            error,
+            path: path.clone(),
+            message,
+        })
+    };
+
+    for name in BUILTIN_NAMES {
+        names.insert(name, "");
+    }
+    let configs = std::iter::once((&default_path, default))
+        .chain(chain.iter().map(|(path, config)| (path, config)));
+
+    for (path, config) in configs {
+        let is_default = path == &default_path;
+
+        for cfg in config.iter() {
+            if !cfg.option.is_empty() {
+                if let Err(e) = parse_option(cfg) {
+                    report(
+                        true,
+                        path,
+                        format!(
+                            "Can not parse option `{}` of `{}`: {}",
+                            cfg.option, cfg.id, e
+                        ),
+                    );
+                }
+                match options.get(cfg.id.as_str()) {
+                    Some((first, option)) if *option != cfg.option => report(
+                        true,
+                        path,
+                        format!(
+                            "The id `{}` is defined as `{}` in {:?}, conflict with `{}`",
+                            cfg.id, option, first, cfg.option
+                        ),
+                    ),
+                    Some((first, _)) if *first != path && *first != &default_path => report(
+                        false,
+                        path,
+                        format!(
+                            "The id `{}` is also defined in {:?}, the values are merged",
+                            cfg.id, first
+                        ),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        options.insert(&cfg.id, (path, &cfg.option));
+                    }
+                }
+            }
+            for name in cfg.names().into_iter().filter(|v| !v.is_empty()) {
+                match names.get(name) {
+                    Some(&"") => report(
+                        true,
+                        path,
+                        format!(
+                            "The `{}` of `{}` collides with built-in option",
+                            name, cfg.id
+                        ),
+                    ),
+                    Some(id) if *id != cfg.id => report(
+                        true,
+                        path,
+                        format!("The `{}` of `{}` collides with `{}`", name, cfg.id, id),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        names.insert(name, &cfg.id);
+                    }
+                }
+            }
+            if is_default || WHOLE_IDS.contains(&cfg.id.as_str()) {
+                continue;
+            }
+            for value in cfg.value.iter().flatten() {
+                if value.starts_with('.') {
+                    report(
+                        false,
+                        path,
+                        format!(
+                            "The extension `{}` of `{}` should be written without the leading dot",
+                            value, cfg.id
+                        ),
+                    );
+                }
+                categories.entry(value).or_default().insert(&cfg.id);
+            }
+        }
+    }
+    for (value, ids) in categories {
+        if ids.len() > 1 {
+            let ids: Vec<_> = ids.into_iter().collect();
+            let path = chain.last().map(|(path, _)| path).unwrap_or(&default_path);
+
+            report(
+                false,
+                path,
+                format!(
+                    "The extension `{}` is in multiple categories: {}",
+                    value,
+                    ids.join(", ")
+                ),
+            );
+        }
+    }
+    problems
+}
+
+/// Add the option to an empty parser, return the error of aopt.
+fn parse_option(cfg: &JsonConfig) -> Result<(), aopt::Error> {
+    let mut parser = AFwdParser::default();
+    let collection = JsonOptCollection {
+        opts: vec![cfg.clone()],
+        ..Default::default()
+    };
+
+    collection.append_opts(&mut parser)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::ConfigFormat;
+
+    #[test]
+    fn check_problems() {
+        let default = ConfigFormat::Json
+            .parse(crate::config::default_json_configuration())
+            .unwrap();
+        let parse = |content: &str| ConfigFormat::Json.parse(content).unwrap();
+        let chain = vec![
+            (
+                PathBuf::from("a.json"),
+                parse(
+                    r#"{ "opts": [
+                        { "id": "rust", "option": "-rs=s", "value": [".rs"] },
+                        { "id": "help2", "option": "--help=b" },
+                        { "id": "bad", "option": "-x=?" }
+                    ] }"#,
+                ),
+            ),
+            (
+                PathBuf::from("b.json"),
+                parse(
+                    r#"{ "opts": [
+                        { "id": "rust", "option": "-rust=s" },
+                        { "id": "ferris", "option": "-fe=s", "value": [".rs"] }
+                    ] }"#,
+                ),
+            ),
+        ];
+        let problems: Vec<_> = check(&default, &chain)
+            .into_iter()
+            .map(|v| (v.error, v.message))
+            .collect();
+        let has = |error: bool, message: &str| {
+            problems
+                .iter()
+                .any(|(e, m)| *e == error && m.contains(message))
+        };
+
+        assert!(has(true, "Can not parse option `-x=?` of `bad`"));
+        assert!(has(
+            true,
+            "The `--help` of `help2` collides with built-in option"
+        ));
+        assert!(has(true, "The id `rust` is defined as `-rs=s`"));
+        assert!(has(
+            false,
+            "The extension `.rs` of `rust` should be written without the leading dot"
+        ));
+        assert!(has(
+            false,
+            "The extension `.rs` is in multiple categories: ferris, rust"
+        ));
+        assert_eq!(problems.len(), 6, "{problems:?}");
+    }
+
+    #[test]
+    fn check_default_configuration() {
+        let default = ConfigFormat::Json
+            .parse(crate::config::default_json_configuration())
+            .unwrap();
+
+        assert!(check(&default, &[]).is_empty());
+    }
+}
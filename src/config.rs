@@ -1,37 +1,227 @@
 use crate::json::JsonOptCollection;
 
-use aopt::error;
-use aopt::Error;
+use findsource::FindError;
+use std::path::Path;
 use std::path::PathBuf;
 
 const CONFIG: &str = include_str!("../config.json");
 
+/// The file format of configuration, all of them share the schema of [`JsonOptCollection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+
+    Toml,
+
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// The extensions searched in configuration directories, in order.
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "toml", "yaml", "yml"];
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|v| v.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Guess the format from content when the file has no known extension,
+    /// the comments of JSON5 (`//`, `/* */`), TOML and YAML (`#`) are skipped.
+    pub fn sniff(context: &str) -> Self {
+        let mut rest = context.trim_start();
+
+        loop {
+            if rest.starts_with('#') || rest.starts_with("//") {
+                rest = rest.split_once('\n').map(|v| v.1).unwrap_or_default();
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                rest = comment.split_once("*/").map(|v| v.1).unwrap_or_default();
+            } else {
+                break;
+            }
+            rest = rest.trim_start();
+        }
+        let first = rest.lines().next().unwrap_or_default().trim();
+
+        if first.starts_with('{') {
+            Self::Json
+        } else if first.starts_with('[') || first.contains(" = ") {
+            Self::Toml
+        } else {
+            Self::Yaml
+        }
+    }
+
+    /// Parse the configuration, the error message contains the position and a snippet of the content.
+    pub fn parse(&self, context: &str) -> Result<JsonOptCollection, String> {
+        match self {
+            // JSON5 accepts the comments, trailing commas and unquoted keys
+            Self::Json => json5::from_str(context).map_err(|e| {
+                let json5::Error::Message { msg, location } = e;
+
+                // the syntax error is a pest report, which has the position and snippet already
+                if msg.trim_start().starts_with("-->") {
+                    format!("syntax error\n{}", msg.trim_end())
+                } else {
+                    describe(context, &msg, location.map(|v| (v.line, v.column)))
+                }
+            }),
+            Self::Toml => toml::from_str(context).map_err(|e| {
+                let position = e.span().map(|v| line_column(context, v.start));
+
+                describe(context, e.message().trim_end(), position)
+            }),
+            Self::Yaml => serde_yaml::from_str(context).map_err(|e| {
+                let position = e.location().map(|v| (v.line(), v.column()));
+                let msg = e.to_string();
+                let msg = msg.split(" at line ").next().unwrap_or_default();
+
+                describe(context, msg, position)
+            }),
+        }
+    }
+}
+
+/// Convert the byte offset to one-based line and column.
+fn line_column(context: &str, offset: usize) -> (usize, usize) {
+    let before = &context[..offset.min(context.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+
+    (line, column)
+}
+
+/// Format the error message with the line and column, and the snippet of content.
+fn describe(context: &str, msg: &str, position: Option<(usize, usize)>) -> String {
+    match position {
+        Some((line, column)) => {
+            let text = context
+                .lines()
+                .nth(line.saturating_sub(1))
+                .unwrap_or_default();
+            let number = line.to_string();
+            let pad = " ".repeat(number.len());
+            let mark = " ".repeat(column.saturating_sub(1));
+
+            format!("{msg}\n{pad}--> line {line}, column {column}\n{pad} |\n{number} | {text}\n{pad} | {mark}^")
+        }
+        None => msg.to_owned(),
+    }
+}
+
+/// Load the configuration by name or path, the configurations in `extends` are merged first.
 pub fn try_to_load_configuration2(
     config_directories: &[Option<std::path::PathBuf>],
     name: &str,
-) -> Result<(PathBuf, JsonOptCollection), Error> {
-    let cfg_name = format!("{name}.json");
+) -> Result<(PathBuf, JsonOptCollection), FindError> {
+    let chain = load_configuration_chain(config_directories, name)?;
+    let path = chain
+        .last()
+        .map(|(path, _)| path.clone())
+        .unwrap_or_default();
+    let mut merged = JsonOptCollection::default();
+
+    for (_, config) in chain {
+        merged.merge(config);
+    }
+    Ok((path, merged))
+}
+
+/// Load the configuration and the configurations in its `extends`, in the merging order.
+/// The paths are canonicalized, the configuration extended multiple times is loaded once.
+pub fn load_configuration_chain(
+    config_directories: &[Option<std::path::PathBuf>],
+    name: &str,
+) -> Result<Vec<(PathBuf, JsonOptCollection)>, FindError> {
+    let mut chain = vec![];
+
+    resolve_configuration(config_directories, None, name, &mut vec![], &mut chain)?;
+    Ok(chain)
+}
+
+/// Load the extended configurations recursively, the `loading` stack is used to detect cycles.
+/// The `base` is the directory of extending configuration, it is searched before the others.
+fn resolve_configuration(
+    config_directories: &[Option<std::path::PathBuf>],
+    base: Option<&Path>,
+    name: &str,
+    loading: &mut Vec<PathBuf>,
+    chain: &mut Vec<(PathBuf, JsonOptCollection)>,
+) -> Result<(), FindError> {
+    let mut dirs = vec![base.map(Path::to_path_buf)];
+    let local = base
+        .map(|v| v.join(name))
+        .filter(|v| v.is_file())
+        .and_then(|v| v.to_str().map(String::from));
+
+    dirs.extend_from_slice(config_directories);
+    let (path, mut config) = load_configuration_file(&dirs, local.as_deref().unwrap_or(name))?;
+    let key = dunce::canonicalize(&path).unwrap_or_else(|_| path.clone());
+
+    if loading.contains(&key) {
+        let mut cycle: Vec<_> = loading.iter().map(|v| format!("{:?}", v)).collect();
+
+        cycle.push(format!("{:?}", key));
+        return Err(FindError::invalid_config(
+            path,
+            format!("Cyclic extends: {}", cycle.join(" -> ")),
+        ));
+    }
+    if chain.iter().any(|(path, _)| path == &key) {
+        return Ok(());
+    }
+    loading.push(key.clone());
+    for parent in std::mem::take(&mut config.extends) {
+        resolve_configuration(config_directories, key.parent(), &parent, loading, chain)?;
+    }
+    loading.pop();
+    chain.push((key, config));
+    Ok(())
+}
+
+/// Find the configuration in directories and parse it, the `extends` is not resolved.
+fn load_configuration_file(
+    config_directories: &[Option<std::path::PathBuf>],
+    name: &str,
+) -> Result<(PathBuf, JsonOptCollection), FindError> {
     let mut config = PathBuf::from(name);
 
     // search in config directories
-    for path in config_directories.iter().flatten() {
-        let handler = path.join(&cfg_name);
+    'search: for path in config_directories.iter().flatten() {
+        for ext in ConfigFormat::EXTENSIONS {
+            let handler = path.join(format!("{name}.{ext}"));
 
-        if handler.is_file() {
-            config = handler;
-            break;
+            if handler.is_file() {
+                config = handler;
+                break 'search;
+            }
         }
     }
     // if argument is a valid path
     if config.is_file() {
-        let context = std::fs::read_to_string(&config)
-            .map_err(|e| error!("Can not read from {:?}: {:?}", &config, e))?;
-
-        Ok((
-            config,
-            serde_json::from_str(&context)
-                .map_err(|e| error!("Invalid configuration format: {e:?}"))?,
-        ))
+        let context = std::fs::read_to_string(&config).map_err(|e| FindError::io(&config, e))?;
+        let format =
+            ConfigFormat::from_path(&config).unwrap_or_else(|| ConfigFormat::sniff(&context));
+
+        match format.parse(&context) {
+            Ok(collection) => Ok((config, collection)),
+            Err(e) => Err(FindError::invalid_config(config, e)),
+        }
     } else {
         let mut error_message = String::from("Can not find configuration file in ");
 
@@ -40,10 +230,33 @@ pub fn try_to_load_configuration2(
             error_message += path.to_str().unwrap_or("None");
             error_message += "' ";
         }
-        Err(Error::raise_error(error_message))
+        Err(FindError::invalid_config(name, error_message))
     }
 }
 
+/// The file names of project configuration, with the extensions of [`ConfigFormat`].
+pub const PROJECT_CONFIG_NAMES: [&str; 2] = [".findsource", ".fs"];
+
+/// Find the project configuration in `start` and its parents,
+/// stop at the directory containing `.git` or the filesystem root.
+pub fn find_project_configuration(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        for name in PROJECT_CONFIG_NAMES {
+            for ext in ConfigFormat::EXTENSIONS {
+                let path = dir.join(format!("{name}.{ext}"));
+
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
 pub fn default_json_configuration() -> &'static str {
     CONFIG
 }
@@ -73,5 +286,146 @@ pub fn get_configuration_directories() -> Vec<Option<std::path::PathBuf>> {
         std::env::var("FS_CONFIG_DIR")
             .ok()
             .map(std::path::PathBuf::from),
+        // find configuration in user configuration directory
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(|v| PathBuf::from(v).join("findsource")),
+        home_directory().map(|v| v.join(".config").join("findsource")),
+        // find configuration in system configuration directory
+        cfg!(unix).then(|| PathBuf::from("/etc/findsource")),
     ]
 }
+
+fn home_directory() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Write the configurations into a new directory under temporary directory.
+    fn write_configs(name: &str, configs: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("findsource-{}-{}", name, std::process::id()));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, content) in configs {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn extends_relative_to_configuration() {
+        let dir = write_configs(
+            "extends-relative",
+            &[
+                (
+                    "a.json",
+                    r#"{ "extends": ["b.json"], "opts": [{ "id": "a", "option": "-a=b" }] }"#,
+                ),
+                ("b.json", r#"{ "opts": [{ "id": "b", "option": "-b=b" }] }"#),
+            ],
+        );
+        let name = dir.join("a.json");
+        let (_, config) = try_to_load_configuration2(&[], name.to_str().unwrap()).unwrap();
+        let ids: Vec<_> = config.iter().map(|v| v.id.as_str()).collect();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(ids, ["b", "a"]);
+    }
+
+    #[test]
+    fn extends_chain() {
+        let dir = write_configs(
+            "extends-chain",
+            &[
+                (
+                    "a.json",
+                    r#"{ "extends": ["b.json", "c.json"], "opts": [] }"#,
+                ),
+                ("b.json", r#"{ "extends": ["c.json"], "opts": [] }"#),
+                ("c.json", r#"{ "opts": [] }"#),
+            ],
+        );
+        let chain = load_configuration_chain(&[Some(dir.clone())], "a").unwrap();
+        let names: Vec<_> = chain
+            .iter()
+            .filter_map(|(path, _)| path.file_name()?.to_str())
+            .collect();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, ["c.json", "b.json", "a.json"]);
+    }
+
+    #[test]
+    fn extends_cycle() {
+        let dir = write_configs(
+            "extends-cycle",
+            &[
+                ("a.json", r#"{ "extends": ["b"], "opts": [] }"#),
+                ("b.json", r#"{ "extends": ["a"], "opts": [] }"#),
+            ],
+        );
+        let error = try_to_load_configuration2(&[Some(dir.clone())], "a").unwrap_err();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.to_string().contains("Cyclic extends"));
+    }
+
+    #[test]
+    fn parse_toml_and_yaml() {
+        let toml = "[[opts]]\nid = \"rust\"\noption = \"-rs=s\"\nvalue = [\"rs\"]\n";
+        let yaml = "opts:\n  - id: rust\n    option: -rs=s\n    value: [rs]\n";
+
+        for (format, content) in [(ConfigFormat::Toml, toml), (ConfigFormat::Yaml, yaml)] {
+            let config = format.parse(content).unwrap();
+
+            assert_eq!(ConfigFormat::sniff(content), format);
+            assert_eq!(config[0].id, "rust");
+            assert_eq!(config[0].value, Some(vec![String::from("rs")]));
+        }
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.yml")),
+            Some(ConfigFormat::Yaml)
+        );
+        assert!(ConfigFormat::Toml
+            .parse("opts = 1\n")
+            .unwrap_err()
+            .contains("--> line 1, column 8"));
+    }
+
+    #[test]
+    fn parse_json5() {
+        let content = "// rust\n/* block */ {\n  opts: [{ id: 'rust', option: '-rs=s', },],\n}\n";
+        let config = ConfigFormat::Json.parse(content).unwrap();
+
+        assert_eq!(ConfigFormat::sniff(content), ConfigFormat::Json);
+        assert_eq!(config[0].id, "rust");
+
+        let error = ConfigFormat::Json
+            .parse("{\n  \"opts\": [\n    { \"id\": 1 }\n  ]\n}\n")
+            .unwrap_err();
+
+        assert!(error.contains("--> line 3"), "{error}");
+    }
+
+    #[test]
+    fn project_configuration_stops_at_git() {
+        let dir = write_configs("project", &[(".fs.toml", "opts = []\n")]);
+        let nested = dir.join("repo/src");
+
+        std::fs::create_dir_all(&nested).unwrap();
+        let found = find_project_configuration(&nested);
+
+        std::fs::create_dir_all(dir.join("repo/.git")).unwrap();
+        let stopped = find_project_configuration(&nested);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, Some(dir.join(".fs.toml")));
+        assert_eq!(stopped, None);
+    }
+}
@@ -0,0 +1,385 @@
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use notify::RecursiveMode;
+use notify::Watcher;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::BufWriter;
+use tokio::io::Lines;
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::UnixListener;
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+
+use crate::error::FindError;
+use crate::finder::Filter;
+use crate::index::Index;
+use crate::matcher::Candidate;
+use crate::note;
+
+/// The query send to daemon, the `root` must be canonical.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub root: PathBuf,
+
+    pub filter: Filter,
+}
+
+/// The daemon reply one response for every matched file, the `path` is relative to the root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Response {
+    Matched {
+        path: PathBuf,
+
+        category: Option<String>,
+    },
+
+    Error(String),
+}
+
+/// Get the socket of daemon, it can be set by `FS_DAEMON_SOCKET`.
+/// The default is `$XDG_RUNTIME_DIR/findsource.sock`,
+/// or `findsource-UID/daemon.sock` in the temporary directory, which is only accessible by current user.
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("FS_DAEMON_SOCKET").filter(|v| !v.is_empty()) {
+        return PathBuf::from(path);
+    }
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("findsource.sock"),
+        None => std::env::temp_dir()
+            .join(format!("findsource-{}", current_uid()))
+            .join("daemon.sock"),
+    }
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: geteuid is always successful
+    unsafe { libc::geteuid() }
+}
+
+fn permission_denied(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::PermissionDenied, message)
+}
+
+/// Create the directory of socket if it is not exist, only current user can access it.
+/// Other users must not be able to replace the socket, the directory should be owned
+/// by current user and not accessible by others, or be sticky such as `/tmp`.
+fn prepare_socket_directory(socket: &Path) -> std::io::Result<()> {
+    let Some(dir) = socket.parent().filter(|v| !v.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+
+    if !dir.exists() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    let meta = std::fs::metadata(dir)?;
+    let private = meta.uid() == current_uid() && meta.mode() & 0o077 == 0;
+    let sticky = meta.mode() & 0o1000 != 0;
+
+    if private || sticky {
+        Ok(())
+    } else {
+        Err(permission_denied(format!(
+            "The directory {dir:?} of daemon socket is accessible by other users"
+        )))
+    }
+}
+
+/// Return error if the socket is not created by current user, others may pretend to be the daemon.
+fn check_socket_owner(socket: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(socket)?.uid() != current_uid() {
+        return Err(permission_denied(format!(
+            "The daemon socket {socket:?} is owned by other user"
+        )));
+    }
+    Ok(())
+}
+
+pub struct Client {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+}
+
+impl Client {
+    /// Connect to the daemon, the socket and the daemon must belong to current user.
+    pub async fn connect(socket: &Path, request: &Request) -> std::io::Result<Self> {
+        check_socket_owner(socket)?;
+        let stream = UnixStream::connect(socket).await?;
+
+        if stream.peer_cred()?.uid() != current_uid() {
+            return Err(permission_denied(format!(
+                "The daemon on {socket:?} is run by other user"
+            )));
+        }
+        let (reader, mut writer) = stream.into_split();
+        let mut line = serde_json::to_string(request)?;
+
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+        writer.shutdown().await?;
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+        })
+    }
+
+    /// Receive next matched file from daemon.
+    pub async fn next(&mut self) -> std::io::Result<Option<(PathBuf, Option<String>)>> {
+        match self.lines.next_line().await? {
+            Some(line) => match serde_json::from_str(&line)? {
+                Response::Matched { path, category } => Ok(Some((path, category))),
+                Response::Error(e) => Err(std::io::Error::other(e)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// The tree cached by daemon, it is marked dirty when anything changed under the root.
+struct Tree {
+    root: PathBuf,
+
+    /// The snapshot of index, the queries keep using it when the index is updated.
+    index: Mutex<Arc<Index>>,
+
+    dirty: AtomicBool,
+}
+
+/// Cache the index of roots in memory, watch them and serve the queries.
+pub async fn serve(roots: Vec<PathBuf>, socket: PathBuf, debug: bool) -> Result<(), FindError> {
+    let mut trees = vec![];
+
+    for root in roots {
+        let root = dunce::canonicalize(&root).map_err(|e| FindError::io(&root, e))?;
+        let index_root = root.clone();
+        // the hidden files are filtered by the query
+        let index = tokio::task::spawn_blocking(move || Index::build(&index_root, true))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|v| v)
+            .map_err(|e| FindError::io(&root, e))?;
+
+        if debug {
+            note!("INFO: ... caching {:?}: {} files", root, index.file_count());
+        }
+        trees.push(Tree {
+            root,
+            index: Mutex::new(Arc::new(index)),
+            dirty: AtomicBool::new(false),
+        });
+    }
+    let trees = Arc::new(trees);
+    let watch_trees = Arc::clone(&trees);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            for tree in watch_trees.iter() {
+                if event.paths.iter().any(|v| v.starts_with(&tree.root)) {
+                    tree.dirty.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    })
+    .map_err(|e| FindError::io(&socket, std::io::Error::other(e)))?;
+
+    for tree in trees.iter() {
+        watcher
+            .watch(&tree.root, RecursiveMode::Recursive)
+            .map_err(|e| FindError::io(&tree.root, std::io::Error::other(e)))?;
+    }
+    prepare_socket_directory(&socket).map_err(|e| FindError::io(&socket, e))?;
+    if socket.exists() {
+        if UnixStream::connect(&socket).await.is_ok() {
+            return Err(FindError::io(
+                &socket,
+                std::io::Error::new(std::io::ErrorKind::AddrInUse, "Daemon is already running"),
+            ));
+        }
+        tokio::fs::remove_file(&socket)
+            .await
+            .map_err(|e| FindError::io(&socket, e))?;
+    }
+    let listener = UnixListener::bind(&socket).map_err(|e| FindError::io(&socket, e))?;
+
+    note!("INFO: ... daemon listening on {:?}", socket);
+    loop {
+        tokio::select! {
+            ret = listener.accept() => {
+                let (stream, _) = ret.map_err(|e| FindError::io(&socket, e))?;
+                let trees = Arc::clone(&trees);
+
+                tokio::spawn(async move {
+                    if let Err(e) = handle_query(stream, trees).await {
+                        note!("ERROR: Can not handle the query: {:?}", e);
+                    }
+                });
+            }
+            _ = tokio::signal::ctrl_c() => {
+                break;
+            }
+        }
+    }
+    tokio::fs::remove_file(&socket)
+        .await
+        .map_err(|e| FindError::io(&socket, e))
+}
+
+/// Reply the matched files of query, every response is written once it is produced.
+async fn handle_query(stream: UnixStream, trees: Arc<Vec<Tree>>) -> std::io::Result<()> {
+    let (reader, writer) = stream.into_split();
+    let mut writer = BufWriter::new(writer);
+    let mut lines = BufReader::new(reader).lines();
+
+    if let Some(line) = lines.next_line().await? {
+        let request: Request = serde_json::from_str(&line)?;
+        let filter = request.filter;
+
+        match trees.iter().find(|v| request.root.starts_with(&v.root)) {
+            Some(tree) => {
+                let index = tree.snapshot().await?;
+                let prefix = request
+                    .root
+                    .strip_prefix(&tree.root)
+                    .unwrap_or(Path::new(""));
+
+                for (rel, file) in index.files() {
+                    let Ok(rel) = rel.strip_prefix(prefix) else {
+                        continue;
+                    };
+
+                    if filter.skip_hidden(rel, &file.name) {
+                        continue;
+                    }
+                    let path = tree.root.join(prefix).join(rel).join(&file.name);
+                    let category = filter.category(&Candidate::new(&path, &file.name));
+
+                    if category.is_some() || filter.invert {
+                        let response = Response::Matched {
+                            path: rel.join(&file.name),
+                            category: category.map(String::from),
+                        };
+
+                        write_response(&mut writer, &response).await?;
+                    }
+                }
+            }
+            None => {
+                let response =
+                    Response::Error(format!("Path {:?} is not served by daemon", request.root));
+
+                write_response(&mut writer, &response).await?;
+            }
+        }
+    }
+    writer.flush().await
+}
+
+async fn write_response(
+    writer: &mut BufWriter<OwnedWriteHalf>,
+    response: &Response,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(response)?;
+
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+impl Tree {
+    /// Update the index if anything changed, return the snapshot of it.
+    /// The lock is released before replying, so the queries do not wait for each other.
+    async fn snapshot(&self) -> std::io::Result<Arc<Index>> {
+        let mut index = self.index.lock().await;
+
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            let mut updated = Index::clone(&index);
+            let root = self.root.clone();
+            let ret = tokio::task::spawn_blocking(move || updated.update(&root).map(|_| updated))
+                .await
+                .map_err(std::io::Error::other)
+                .and_then(|v| v);
+
+            match ret {
+                Ok(updated) => *index = Arc::new(updated),
+                Err(e) => {
+                    // keep the old index, it is updated again by next query
+                    self.dirty.store(true, Ordering::SeqCst);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Arc::clone(&index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn private_socket_directory() {
+        let dir = std::env::temp_dir().join(format!("findsource-socket-{}", std::process::id()));
+
+        prepare_socket_directory(&dir.join("private").join("daemon.sock")).unwrap();
+        assert_eq!(
+            std::fs::metadata(dir.join("private")).unwrap().mode() & 0o777,
+            0o700
+        );
+        std::fs::set_permissions(
+            dir.join("private"),
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )
+        .unwrap();
+        assert!(prepare_socket_directory(&dir.join("private").join("daemon.sock")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn query_daemon() {
+        let dir = std::env::temp_dir().join(format!("findsource-daemon-{}", std::process::id()));
+        let root = dir.join("root");
+        let socket = dir.join("run").join("daemon.sock");
+
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.rs"), "").unwrap();
+        std::fs::write(root.join("a.c"), "").unwrap();
+        std::fs::write(root.join("sub").join("b.rs"), "").unwrap();
+        let server = tokio::spawn(serve(vec![root.clone()], socket.clone(), false));
+
+        while !socket.exists() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let request = Request {
+            root: dunce::canonicalize(&root).unwrap(),
+            filter: Filter::new(
+                false,
+                false,
+                false,
+                HashMap::default(),
+                HashMap::from([(String::from("rs"), String::from("rust"))]),
+            ),
+        };
+        let mut client = Client::connect(&socket, &request).await.unwrap();
+        let mut paths = vec![];
+
+        while let Some((path, category)) = client.next().await.unwrap() {
+            assert_eq!(category.as_deref(), Some("rust"));
+            paths.push(path);
+        }
+        paths.sort();
+        server.abort();
+        assert_eq!(paths, [Path::new("a.rs"), Path::new("sub/b.rs")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinSet;
+
+use crate::hash::hash_file;
+use crate::hash::HashAlgorithm;
+use crate::note;
+
+/// A set of files which have same size and content.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub size: u64,
+
+    pub hash: String,
+
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    files: Vec<String>,
+}
+
+impl Duplicates {
+    pub fn add_file(&mut self, path: String) -> &mut Self {
+        self.files.push(path);
+        self
+    }
+
+    /// Group the files by size first, then hash the files which have same size in parallel.
+    pub async fn find(self) -> color_eyre::Result<Vec<DuplicateGroup>> {
+        let mut sizes = BTreeMap::<u64, Vec<String>>::default();
+        let mut hashes = BTreeMap::<(u64, String), Vec<String>>::default();
+        let mut workers = JoinSet::new();
+
+        for path in self.files {
+            match tokio::fs::metadata(&path).await {
+                Ok(meta) => sizes.entry(meta.len()).or_default().push(path),
+                Err(e) => {
+                    note!("ERROR: Can not get size of file `{}`: {:?}", path, e);
+                }
+            }
+        }
+        for (size, files) in sizes.into_iter().filter(|(_, v)| v.len() > 1) {
+            for path in files {
+                workers.spawn_blocking(move || {
+                    let hash = hash_file(&path, HashAlgorithm::Blake3);
+
+                    (size, path, hash)
+                });
+            }
+        }
+        while let Some(ret) = workers.join_next().await {
+            let (size, path, hash) = ret?;
+
+            match hash {
+                Ok(hash) => hashes.entry((size, hash)).or_default().push(path),
+                Err(e) => {
+                    note!("ERROR: Can not hash file `{}`: {:?}", path, e);
+                }
+            }
+        }
+
+        Ok(hashes
+            .into_iter()
+            .filter(|(_, v)| v.len() > 1)
+            .map(|((size, hash), mut files)| {
+                files.sort();
+                DuplicateGroup { size, hash, files }
+            })
+            .collect())
+    }
+}
+
+/// Display the duplicate groups, every group is separated by an empty line.
+pub fn display(groups: &[DuplicateGroup]) -> String {
+    groups
+        .iter()
+        .map(|group| {
+            let mut ret = format!("{} bytes each:\n", group.size);
+
+            for file in group.files.iter() {
+                ret += file;
+                ret += "\n";
+            }
+            ret
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn find_duplicate_files() {
+        let dir = std::env::temp_dir().join(format!("findsource-duplicate-{}", std::process::id()));
+        let mut duplicates = Duplicates::default();
+
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in [("a", "same"), ("b", "same"), ("c", "diff"), ("d", "longer")] {
+            let path = dir.join(name);
+
+            std::fs::write(&path, content).unwrap();
+            duplicates.add_file(path.to_string_lossy().into_owned());
+        }
+        let groups = duplicates.find().await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].size, 4);
+        assert!(groups[0].files[0].ends_with('a') && groups[0].files[1].ends_with('b'));
+    }
+}
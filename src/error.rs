@@ -0,0 +1,168 @@
+use std::fmt::Display;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The error occurred while searching the files.
+#[derive(Debug)]
+pub enum FindError {
+    PermissionDenied {
+        path: PathBuf,
+    },
+
+    NotFound {
+        path: PathBuf,
+    },
+
+    /// The symbolic link points to its ancestor directory.
+    SymlinkLoop {
+        path: PathBuf,
+    },
+
+    /// The configuration can not be found or parsed.
+    InvalidConfig {
+        path: PathBuf,
+
+        message: String,
+    },
+
+    /// Other errors when access the path.
+    Io {
+        path: PathBuf,
+
+        error: std::io::Error,
+    },
+
+    /// The receiver of matched files went away.
+    Cancelled,
+}
+
+impl FindError {
+    /// Classify the io error by its kind.
+    pub fn io(path: impl Into<PathBuf>, error: std::io::Error) -> Self {
+        let path = path.into();
+
+        match error.kind() {
+            ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+            ErrorKind::NotFound => Self::NotFound { path },
+            _ => Self::Io { path, error },
+        }
+    }
+
+    pub fn invalid_config(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::InvalidConfig {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    /// The path caused the error.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::PermissionDenied { path }
+            | Self::NotFound { path }
+            | Self::SymlinkLoop { path }
+            | Self::InvalidConfig { path, .. }
+            | Self::Io { path, .. } => Some(path),
+            Self::Cancelled => None,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+}
+
+impl Display for FindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PermissionDenied { path } => write!(f, "Permission denied: {:?}", path),
+            Self::NotFound { path } => write!(f, "No such file or directory: {:?}", path),
+            Self::SymlinkLoop { path } => write!(f, "Symbolic link loop detected: {:?}", path),
+            Self::InvalidConfig { path, message } => {
+                write!(f, "Invalid configuration {:?}: {}", path, message)
+            }
+            Self::Io { path, error } => write!(f, "Can not access {:?}: {}", path, error),
+            Self::Cancelled => write!(f, "The searching is cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for FindError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Return an error if the path is a symbolic link to its ancestor directory.
+pub(crate) fn check_symlink_loop(path: &Path) -> Result<(), FindError> {
+    let is_symlink = std::fs::symlink_metadata(path)
+        .map(|v| v.file_type().is_symlink())
+        .unwrap_or_default();
+
+    if is_symlink {
+        let target = dunce::canonicalize(path);
+        let parent = path
+            .parent()
+            .map(|v| {
+                if v.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    v
+                }
+            })
+            .map(dunce::canonicalize);
+
+        if let (Ok(target), Some(Ok(parent))) = (target, parent) {
+            if parent.starts_with(target) {
+                return Err(FindError::SymlinkLoop {
+                    path: path.to_path_buf(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify_io_error() {
+        let error = FindError::io("a.rs", std::io::Error::from(ErrorKind::PermissionDenied));
+
+        assert!(matches!(error, FindError::PermissionDenied { .. }));
+        assert_eq!(error.path(), Some(Path::new("a.rs")));
+        assert!(matches!(
+            FindError::io("a.rs", std::io::Error::from(ErrorKind::NotFound)),
+            FindError::NotFound { .. }
+        ));
+        assert!(matches!(
+            FindError::io("a.rs", std::io::Error::other("broken")),
+            FindError::Io { .. }
+        ));
+        assert_eq!(FindError::Cancelled.path(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detect_symlink_loop() {
+        let root = std::env::temp_dir().join(format!("findsource-loop-{}", std::process::id()));
+
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("sub").join("loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("sub"), root.join("link")).unwrap();
+
+        assert!(matches!(
+            check_symlink_loop(&root.join("sub").join("loop")),
+            Err(FindError::SymlinkLoop { .. })
+        ));
+        assert!(check_symlink_loop(&root.join("link")).is_ok());
+        assert!(check_symlink_loop(&root.join("sub")).is_ok());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,141 +1,270 @@
-use aopt::prelude::AFwdParser;
-use aopt::prelude::*;
-use aopt::HashMap;
-use std::collections::HashSet;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs::read_dir;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc::Sender;
-
+use tokio_stream::wrappers::ReceiverStream;
+
+#[cfg(feature = "archive")]
+use crate::archive::list_entries;
+#[cfg(feature = "archive")]
+use crate::archive::ArchiveKind;
+#[cfg(feature = "archive")]
+use crate::archive::ENTRY_SEPARATOR;
+use crate::cancel::CancelToken;
+#[cfg(all(unix, feature = "daemon"))]
+use crate::daemon;
+use crate::error::check_symlink_loop;
+use crate::error::FindError;
+use crate::git;
+use crate::git::GitSelect;
+#[cfg(feature = "index")]
+use crate::index::Index;
+use crate::matcher::Candidate;
+use crate::matcher::ExtensionMatcher;
+use crate::matcher::Matcher;
+use crate::matcher::WholeMatcher;
 use crate::note;
 use crate::start_worker;
 
+/// The stream of matched files returned by [`FinderBuilder::find`](crate::FinderBuilder::find).
+pub type MatchStream = ReceiverStream<Result<Matched, FindError>>;
+
+/// Search the files under roots, it is created by [`FinderBuilder`](crate::FinderBuilder).
 pub struct Finder {
-    full: bool,
+    pub(crate) full: bool,
 
     pub(crate) debug: bool,
 
-    verb: bool,
+    pub(crate) verb: bool,
+
+    pub(crate) reverse: bool,
+
+    #[cfg(feature = "archive")]
+    pub(crate) archive: bool,
+
+    #[cfg(feature = "index")]
+    pub(crate) index: bool,
 
-    hidden: bool,
+    #[cfg(feature = "daemon")]
+    pub(crate) daemon: bool,
 
-    reverse: bool,
+    pub(crate) git: Vec<GitSelect>,
+
+    pub(crate) filter: Arc<Filter>,
+
+    pub(crate) sender: Sender<Result<Matched, FindError>>,
+
+    pub(crate) token: CancelToken,
+}
 
-    igcase: bool,
+/// Hold the matching rules of file, it can be shared after the searching end.
+///
+/// The whole name is checked first, then the extension and the custom matchers.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Filter {
+    pub(crate) hidden: bool,
 
-    invert: bool,
+    pub(crate) invert: bool,
 
-    whos: HashSet<String>,
+    whole: WholeMatcher,
 
-    exts: HashSet<String>,
+    extension: ExtensionMatcher,
 
-    sender: Sender<String>,
+    /// The custom matchers can not be sent to daemon.
+    #[serde(skip)]
+    matchers: Vec<Arc<dyn Matcher>>,
+}
+
+impl Filter {
+    pub fn new(
+        hidden: bool,
+        igcase: bool,
+        invert: bool,
+        whos: HashMap<String, String>,
+        exts: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            hidden,
+            invert,
+            whole: WholeMatcher::new(whos, igcase),
+            extension: ExtensionMatcher::new(exts, igcase),
+            matchers: vec![],
+        }
+    }
+
+    pub fn add_matcher(&mut self, matcher: Arc<dyn Matcher>) -> &mut Self {
+        self.matchers.push(matcher);
+        self
+    }
+
+    pub fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn invert(&self) -> bool {
+        self.invert
+    }
+
+    /// The whole file names and their category id.
+    pub fn whos(&self) -> &HashMap<String, String> {
+        self.whole.whos()
+    }
+
+    /// The file extensions and their category id.
+    pub fn exts(&self) -> &HashMap<String, String> {
+        self.extension.exts()
+    }
+
+    pub fn has_matchers(&self) -> bool {
+        !self.matchers.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.whole.is_empty() && self.extension.is_empty() && self.matchers.is_empty()
+    }
+
+    /// Return the category id of given file.
+    pub fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.whole
+            .category(file)
+            .or_else(|| self.extension.category(file))
+            .or_else(|| self.matchers.iter().find_map(|v| v.category(file)))
+    }
+
+    /// Return true if the file or any directory of it is hidden, and hidden file is not allowed.
+    pub fn skip_hidden(&self, rel: &Path, file_name: &str) -> bool {
+        !self.hidden
+            && rel
+                .components()
+                .filter_map(|v| v.as_os_str().to_str())
+                .chain(std::iter::once(file_name))
+                .any(|v| v.starts_with('.'))
+    }
+}
+
+/// A matched file and the category it belongs to.
+#[derive(Debug, Clone, Serialize)]
+pub struct Matched {
+    pub path: String,
+
+    pub category: Option<String>,
+
+    /// Is the file an entry inside the archive.
+    pub entry: bool,
 }
 
 impl Finder {
-    pub async fn new(
-        opts: HashMap<String, String>,
-        parser: AFwdParser<'_>,
-        debug: bool,
-        verb: bool,
-        sender: Sender<String>,
-    ) -> color_eyre::Result<Self> {
-        let mut whos = HashSet::<String>::default();
-        let mut exts = HashSet::<String>::default();
-
-        let only = parser.find_val::<String>("--only");
-        let exclude = parser.find_vals::<String>("--Exclude");
-        let ex_exts = parser.find_vals::<String>("--Extension");
-        let ex_whos = parser.find_vals::<String>("--Whole");
-        let whole = parser.find_vals::<String>("--whole");
-        let extension = parser.find_vals::<String>("--extension");
-        let full = *parser.find_val("--full")?;
-
-        let igcase = *parser.find_val("--ignore-case")?;
-        let reverse = !*parser.find_val::<bool>("--/reverse")?;
-        let hidden = *parser.find_val("--hidden")?;
-        let invert = *parser.find_val("--invert")?;
-
-        let only_checker = |name1: &str, name2: &str| -> bool {
-            if let Ok(only) = only {
-                only.eq(name1) || only.eq(name2)
-            } else {
-                true
-            }
-        };
-        let exclude_checker = move |name1: &str, name2: &str| -> bool {
-            if let Ok(exclude) = exclude {
-                exclude.iter().any(|v| v.eq(name1) || v.eq(name2))
-            } else {
-                false
-            }
-        };
+    /// Start searching the roots, the matched files are sent to the sender of finder.
+    ///
+    /// It must be called within a tokio runtime.
+    pub fn search(self, roots: Vec<PathBuf>) {
+        let finder = Arc::new(self);
+
+        for path in roots {
+            let inner_finder = Arc::clone(&finder);
+
+            tokio::spawn(start_worker!(
+                inner_finder,
+                path,
+                Finder::find_in_directory_first
+            ));
+        }
+    }
 
-        if only_checker("whole", "w") && !exclude_checker("whole", "w") {
-            if let Ok(whole) = whole {
-                for ext in whole {
-                    whos.insert(ext.clone());
-                }
-            }
+    pub fn is_empty(&self) -> bool {
+        self.filter.is_empty()
+    }
+
+    pub fn filter(&self) -> Arc<Filter> {
+        Arc::clone(&self.filter)
+    }
+
+    /// Cancel the token to stop the searching.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    /// Return [`FindError::Cancelled`] if the searching is cancelled.
+    pub fn check_cancelled(&self) -> Result<(), FindError> {
+        if self.token.is_cancelled() {
+            Err(FindError::Cancelled)
+        } else {
+            Ok(())
         }
-        if only_checker("extension", "e") && !exclude_checker("extension", "e") {
-            if let Ok(extension) = extension {
-                for ext in extension {
-                    exts.insert(ext.clone());
-                }
-            }
+    }
+
+    pub fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.filter.category(file)
+    }
+
+    /// Send the matched file, return [`FindError::Cancelled`] if the receiver went away.
+    pub async fn send(&self, matched: Matched) -> Result<(), FindError> {
+        self.check_cancelled()?;
+        self.sender.send(Ok(matched)).await.map_err(|_| {
+            self.token.cancel();
+            FindError::Cancelled
+        })
+    }
+
+    /// Send the error to the receiver, the [`FindError::Cancelled`] is ignored.
+    pub async fn report(&self, error: FindError) {
+        if !error.is_cancelled() {
+            let _ = self.sender.send(Err(error)).await;
         }
-        for (id, opt) in opts {
-            if only_checker(id.as_str(), "") && !exclude_checker(id.as_str(), "") {
-                if let Ok(opt_exts) = parser.find_vals::<String>(opt.as_str()) {
-                    for ext in opt_exts {
-                        exts.insert(ext.clone());
+    }
+
+    pub async fn find_in_directory_first(self: Arc<Self>, path: PathBuf) -> Result<(), FindError> {
+        if !self.git.is_empty() {
+            return self.find_in_git(path).await;
+        }
+        // the daemon has no entries of archive
+        #[cfg(feature = "daemon")]
+        if self.daemon
+            && self.reverse
+            && !self.search_archives()
+            && path.is_dir()
+            && !self.filter.has_matchers()
+        {
+            #[cfg(unix)]
+            {
+                let request = daemon::Request {
+                    root: dunce::canonicalize(&path).map_err(|e| FindError::io(&path, e))?,
+                    filter: Filter::clone(&self.filter),
+                };
+
+                match daemon::Client::connect(&daemon::socket_path(), &request).await {
+                    Ok(client) => return self.find_in_daemon(path, client).await,
+                    Err(e) => {
+                        // the socket may be created by other user
+                        if self.debug || e.kind() == std::io::ErrorKind::PermissionDenied {
+                            note!("WARN: daemon is not available, searching directly: {:?}", e);
+                        }
                     }
                 }
             }
         }
-        if let Ok(ex_exts) = ex_exts {
-            for ext in ex_exts {
-                exts.remove(ext);
-            }
+        #[cfg(feature = "index")]
+        if self.index && self.reverse && path.is_dir() {
+            return self.find_in_index(path).await;
         }
-        if let Ok(ex_whos) = ex_whos {
-            for ext in ex_whos {
-                whos.remove(ext);
-            }
-        }
-        if igcase {
-            exts = exts.into_iter().map(|v| v.to_lowercase()).collect();
-            whos = whos.into_iter().map(|v| v.to_lowercase()).collect();
-        }
-        if debug {
-            note!("INFO: match whole filename : {:?}", whos);
-            note!("INFO: match file extension : {:?}", exts);
-        }
-        Ok(Self {
-            full,
-            debug,
-            verb,
-            hidden,
-            reverse,
-            igcase,
-            invert,
-            whos,
-            exts,
-            sender,
-        })
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.whos.is_empty() && self.exts.is_empty()
+        self.find_in_directory_impl(path, true).await
     }
 
-    pub async fn find_in_directory_first(self: Arc<Self>, path: PathBuf) -> color_eyre::Result<()> {
-        self.find_in_directory_impl(path, true).await
+    /// Are the entries of archive searched, it is false without the `archive` feature.
+    #[cfg(feature = "daemon")]
+    fn search_archives(&self) -> bool {
+        #[cfg(feature = "archive")]
+        return self.archive;
+        #[cfg(not(feature = "archive"))]
+        false
     }
 
-    pub async fn find_in_directory_left(self: Arc<Self>, path: PathBuf) -> color_eyre::Result<()> {
+    pub async fn find_in_directory_left(self: Arc<Self>, path: PathBuf) -> Result<(), FindError> {
         self.find_in_directory_impl(path, false).await
     }
 
@@ -143,36 +272,28 @@ impl Finder {
         self: Arc<Self>,
         path: PathBuf,
         first: bool,
-    ) -> color_eyre::Result<()> {
+    ) -> Result<(), FindError> {
         let debug = self.debug;
         let verbose = self.verb;
         let reverse = self.reverse;
 
+        self.check_cancelled()?;
         if debug && verbose {
             note!("INFO: search file in path: {:?}", path);
         }
-        let meta = tokio::fs::metadata(&path).await?;
+        let meta = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| FindError::io(&path, e))?;
 
         if reverse && meta.is_dir() {
             if first {
-                tokio::spawn(start_worker!(
-                    self,
-                    path,
-                    Self::process_directory_frist,
-                    "ERROR: Can not access directory `{:?}`: {:?}"
-                ));
+                tokio::spawn(start_worker!(self, path, Self::process_directory_frist));
             } else {
-                tokio::spawn(start_worker!(
-                    self,
-                    path,
-                    Self::process_directory_left,
-                    "ERROR: Can not access directory `{:?}`: {:?}"
-                ));
+                check_symlink_loop(&path)?;
+                tokio::spawn(start_worker!(self, path, Self::process_directory_left));
             }
         } else if meta.is_file() {
-            if let Err(e) = self.process_file(path.clone()).await {
-                note!("ERROR: Can not access file `{:?}`: {:?}", path, e);
-            }
+            self.process_file(path).await?;
         } else if debug {
             note!("WARN: {:?} is not a valid file", path);
         }
@@ -180,12 +301,12 @@ impl Finder {
     }
 
     #[async_recursion::async_recursion]
-    pub async fn process_directory_frist(self: Arc<Self>, path: PathBuf) -> color_eyre::Result<()> {
+    pub async fn process_directory_frist(self: Arc<Self>, path: PathBuf) -> Result<(), FindError> {
         self.process_directory_impl(path, true).await
     }
 
     #[async_recursion::async_recursion]
-    pub async fn process_directory_left(self: Arc<Self>, path: PathBuf) -> color_eyre::Result<()> {
+    pub async fn process_directory_left(self: Arc<Self>, path: PathBuf) -> Result<(), FindError> {
         self.process_directory_impl(path, false).await
     }
 
@@ -194,11 +315,11 @@ impl Finder {
         self: Arc<Self>,
         path: PathBuf,
         first: bool,
-    ) -> color_eyre::Result<()> {
+    ) -> Result<(), FindError> {
         let debug = self.debug;
         let verbose = self.verb;
-        let hidden = self.hidden;
-        let path = if first || !is_file_hidden(&path).await? || hidden {
+        let hidden = self.filter.hidden;
+        let path = if first || hidden || !is_file_hidden(&path).await? {
             Some(path)
         } else {
             if debug {
@@ -211,52 +332,67 @@ impl Finder {
             if debug {
                 note!("INFO: checking directory {:?}", path);
             }
-            let mut entries = read_dir(path).await?;
+            let mut entries = read_dir(&path).await.map_err(|e| FindError::io(&path, e))?;
 
-            while let Some(entry) = entries.next_entry().await? {
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| FindError::io(&path, e))?
+            {
                 let path = entry.path();
                 let worker_ctx = Arc::clone(&self);
 
+                self.check_cancelled()?;
                 if debug && verbose {
                     note!("INFO: start searching path {:?}", path);
                 }
                 tokio::spawn(start_worker!(
                     worker_ctx,
                     path,
-                    Self::find_in_directory_left,
-                    "ERROR: Can not find file in directory `{:?}`: {:?}"
+                    Self::find_in_directory_left
                 ));
             }
         }
         Ok(())
     }
 
-    pub async fn process_file(self: Arc<Self>, path: PathBuf) -> color_eyre::Result<()> {
+    pub async fn process_file(self: Arc<Self>, path: PathBuf) -> Result<(), FindError> {
         let debug = self.debug;
-        let hidden = self.hidden;
+        let hidden = self.filter.hidden;
         let full = self.full;
-        let igcase = self.igcase;
-        let invert = self.invert;
+        let invert = self.filter.invert;
 
         let may_full_path = if full {
-            dunce::canonicalize(&path)?
+            dunce::canonicalize(&path).map_err(|e| FindError::io(&path, e))?
         } else {
             path.clone()
         };
 
-        if !is_file_hidden(&path).await? || hidden {
+        if hidden || !is_file_hidden(&path).await? {
             if let Some(path_str) = may_full_path.to_str() {
                 if let Some(Some(file_name)) = path.file_name().map(|v| v.to_str()) {
-                    let lower_case = file_name.to_lowercase();
-                    let lower_case = lower_case.as_ref();
-                    let matched = checking_ext(file_name, &self.whos, &self.exts).await
-                        || (igcase && checking_ext(lower_case, &self.whos, &self.exts).await);
+                    let category = self.category(&Candidate::new(&path, file_name));
 
                     if debug {
                         note!("INFO: checking file {}", path_str);
                     }
-                    if matched || invert {
-                        self.sender.send(path_str.to_owned()).await?;
+                    if category.is_some() || invert {
+                        self.send(Matched {
+                            path: path_str.to_owned(),
+                            category: category.map(String::from),
+                            entry: false,
+                        })
+                        .await?;
+                    }
+                    #[cfg(feature = "archive")]
+                    if self.archive {
+                        if let Some(kind) = ArchiveKind::from_name(file_name) {
+                            match self.process_archive(path.clone(), path_str, kind).await {
+                                Err(FindError::Cancelled) => return Err(FindError::Cancelled),
+                                Err(e) => self.report(e).await,
+                                Ok(_) => {}
+                            }
+                        }
                     }
                 }
             }
@@ -265,31 +401,189 @@ impl Finder {
         }
         Ok(())
     }
-}
 
-pub async fn checking_ext(path: &str, whos: &HashSet<String>, exts: &HashSet<String>) -> bool {
-    match path.rfind('.') {
-        None | Some(0) => whos.contains(path),
-        Some(pos) => {
-            let (_, ext) = path.split_at(pos + 1);
+    /// Match the files recorded in the index of root, the index will be refreshed first.
+    #[cfg(feature = "index")]
+    pub async fn find_in_index(self: Arc<Self>, root: PathBuf) -> Result<(), FindError> {
+        let debug = self.debug;
+        let invert = self.filter.invert;
+        let base = if self.full {
+            dunce::canonicalize(&root).map_err(|e| FindError::io(&root, e))?
+        } else {
+            root.clone()
+        };
+        let index_root = root.clone();
+        let hidden = self.filter.hidden;
+        let (index, count, error) =
+            tokio::task::spawn_blocking(move || Index::refresh(&index_root, hidden))
+                .await
+                .map_err(std::io::Error::other)
+                .and_then(|v| v)
+                .map_err(|e| FindError::io(&root, e))?;
+
+        if let Some(e) = error {
+            note!("WARN: Can not save the index of {:?}: {}", root, e);
+        }
+        if debug {
+            note!(
+                "INFO: loading index of {:?}, {} directories re-walked",
+                root,
+                count
+            );
+        }
+        for (rel, file) in index.files() {
+            let path = base.join(rel).join(&file.name);
+
+            if self.filter.skip_hidden(rel, &file.name) {
+                continue;
+            }
+            if let Some(path_str) = path.to_str() {
+                let category = self.category(&Candidate::new(&path, &file.name));
+
+                if category.is_some() || invert {
+                    self.send(Matched {
+                        path: path_str.to_owned(),
+                        category: category.map(String::from),
+                        entry: false,
+                    })
+                    .await?;
+                }
+                #[cfg(feature = "archive")]
+                if self.archive {
+                    if let Some(kind) = ArchiveKind::from_name(&file.name) {
+                        match self.process_archive(path.clone(), path_str, kind).await {
+                            Err(FindError::Cancelled) => return Err(FindError::Cancelled),
+                            Err(e) => self.report(e).await,
+                            Ok(_) => {}
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Match the files selected by git under the root.
+    pub async fn find_in_git(self: Arc<Self>, root: PathBuf) -> Result<(), FindError> {
+        let debug = self.debug;
+        let files = git::list_files(&root, &self.git)
+            .await
+            .map_err(|e| FindError::io(&root, e))?;
+
+        for path in files {
+            let rel = path.strip_prefix(&root).unwrap_or(&path);
+            let dir = rel.parent().unwrap_or(Path::new(""));
+
+            if let Some(Some(file_name)) = path.file_name().map(|v| v.to_str()) {
+                if self.filter.skip_hidden(dir, file_name) {
+                    if debug {
+                        note!("INFO: ignore file {:?}", path);
+                    }
+                    continue;
+                }
+            }
+            if !path.is_file() {
+                continue;
+            }
+            match Arc::clone(&self).process_file(path).await {
+                Err(FindError::Cancelled) => return Err(FindError::Cancelled),
+                Err(e) => self.report(e).await,
+                Ok(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Receive the matched files of root from daemon.
+    #[cfg(all(unix, feature = "daemon"))]
+    pub async fn find_in_daemon(
+        self: Arc<Self>,
+        root: PathBuf,
+        mut client: daemon::Client,
+    ) -> Result<(), FindError> {
+        let base = if self.full {
+            dunce::canonicalize(&root).map_err(|e| FindError::io(&root, e))?
+        } else {
+            root.clone()
+        };
 
-            exts.contains(ext) || whos.contains(path)
+        while let Some((rel, category)) =
+            client.next().await.map_err(|e| FindError::io(&root, e))?
+        {
+            if let Some(path_str) = base.join(rel).to_str() {
+                self.send(Matched {
+                    path: path_str.to_owned(),
+                    category,
+                    entry: false,
+                })
+                .await?;
+            }
         }
+        Ok(())
+    }
+
+    /// Match the file entries in the archive, report them as `archive.zip!/src/lib.rs`.
+    #[cfg(feature = "archive")]
+    pub async fn process_archive(
+        &self,
+        path: PathBuf,
+        path_str: &str,
+        kind: ArchiveKind,
+    ) -> Result<(), FindError> {
+        let debug = self.debug;
+        let hidden = self.filter.hidden;
+        let invert = self.filter.invert;
+
+        if debug {
+            note!("INFO: checking archive {}", path_str);
+        }
+        let archive = path.clone();
+        let entries = tokio::task::spawn_blocking(move || list_entries(&archive, kind))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|v| v)
+            .map_err(|e| FindError::io(&path, e))?;
+
+        for entry in entries {
+            let entry = entry.trim_start_matches("./");
+            let file_name = entry.rsplit('/').next().unwrap_or(entry);
+
+            if !hidden && entry.split('/').any(|v| v.starts_with('.')) {
+                if debug {
+                    note!("INFO: ignore archive entry {}", entry);
+                }
+                continue;
+            }
+            let entry_path = format!("{path_str}{ENTRY_SEPARATOR}{entry}");
+            let category = self.category(&Candidate::entry(Path::new(&entry_path), file_name));
+
+            if category.is_some() || invert {
+                self.send(Matched {
+                    path: entry_path,
+                    category: category.map(String::from),
+                    entry: true,
+                })
+                .await?;
+            }
+        }
+        Ok(())
     }
 }
 
 #[cfg(windows)]
-pub async fn is_file_hidden(path: &PathBuf) -> color_eyre::Result<bool> {
+pub async fn is_file_hidden(path: &PathBuf) -> Result<bool, FindError> {
     use std::os::windows::fs::MetadataExt;
 
-    let meta = tokio::fs::metadata(path).await?;
+    let meta = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| FindError::io(path, e))?;
     let attributes = meta.file_attributes();
 
     Ok((attributes & 0x2) == 0x2)
 }
 
 #[cfg(not(windows))]
-pub async fn is_file_hidden(path: &PathBuf) -> color_eyre::Result<bool> {
+pub async fn is_file_hidden(path: &PathBuf) -> Result<bool, FindError> {
     if let Some(Some(file_name)) = path.file_name().map(|v| v.to_str()) {
         Ok(file_name.starts_with('.'))
     } else {
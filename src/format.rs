@@ -0,0 +1,74 @@
+use std::str::FromStr;
+
+use aopt::error;
+use aopt::Error;
+use findsource::sink::CsvSink;
+use findsource::sink::JsonLinesSink;
+use findsource::sink::LineSink;
+use findsource::sink::NulSink;
+use findsource::Sink;
+
+/// The output format of the reports, such as `--count-lines` and `--duplicates`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(error!("Unknown output format `{s}`, expect text or json")),
+        }
+    }
+}
+
+/// How the matched files are printed, see [`Sink`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    #[default]
+    Lines,
+
+    Nul,
+
+    JsonLines,
+
+    Csv,
+}
+
+impl Output {
+    /// Create the sink writing to stdout, it is flushed per line if stdout is a terminal.
+    pub fn sink(&self) -> std::io::Result<Box<dyn Sink>> {
+        let stdout = std::io::stdout();
+        let tty = atty::is(atty::Stream::Stdout);
+
+        Ok(match self {
+            Self::Lines => Box::new(LineSink::new(stdout).line_buffered(tty)),
+            Self::Nul => Box::new(NulSink::new(stdout).line_buffered(tty)),
+            Self::JsonLines => Box::new(JsonLinesSink::new(stdout).line_buffered(tty)),
+            Self::Csv => Box::new(CsvSink::new(stdout)?.line_buffered(tty)),
+        })
+    }
+}
+
+impl FromStr for Output {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lines" => Ok(Self::Lines),
+            "nul" => Ok(Self::Nul),
+            "jsonl" => Ok(Self::JsonLines),
+            "csv" => Ok(Self::Csv),
+            _ => Err(error!(
+                "Unknown output `{s}`, expect lines, nul, jsonl or csv"
+            )),
+        }
+    }
+}
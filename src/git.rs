@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tokio::process::Command;
+
+/// Select the file universe from git instead of walking the directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitSelect {
+    Tracked,
+
+    Untracked,
+
+    /// Staged or unstaged changes relative to `HEAD`.
+    Modified,
+
+    /// Changes between the revision and working tree.
+    ChangedSince(String),
+}
+
+impl GitSelect {
+    /// The arguments of git command, the revision starts with `-` is rejected
+    /// because git will parse it as an option, such as `--output=FILE`.
+    fn args(&self) -> std::io::Result<Vec<&str>> {
+        Ok(match self {
+            Self::Tracked => vec!["ls-files", "-z"],
+            Self::Untracked => vec!["ls-files", "-z", "--others", "--exclude-standard"],
+            Self::Modified => vec![
+                "diff",
+                "--name-only",
+                "-z",
+                "--relative",
+                "--diff-filter=d",
+                "HEAD",
+            ],
+            Self::ChangedSince(rev) if rev.is_empty() || rev.starts_with('-') => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid git revision `{rev}`"),
+                ));
+            }
+            Self::ChangedSince(rev) => vec![
+                "diff",
+                "--name-only",
+                "-z",
+                "--relative",
+                "--diff-filter=d",
+                rev.as_str(),
+            ],
+        })
+    }
+}
+
+/// List the files selected by git under `path`, the result is joined with the `path`.
+pub async fn list_files(path: &Path, selects: &[GitSelect]) -> std::io::Result<Vec<PathBuf>> {
+    let (dir, pathspec) = if path.is_file() {
+        (
+            path.parent()
+                .filter(|v| !v.as_os_str().is_empty())
+                .unwrap_or(Path::new(".")),
+            path.file_name().unwrap_or(OsStr::new(".")),
+        )
+    } else {
+        (path, OsStr::new("."))
+    };
+    let mut ret = BTreeSet::default();
+
+    for select in selects {
+        let output = Command::new("git")
+            .args(select.args()?)
+            .arg("--")
+            .arg(pathspec)
+            .current_dir(dir)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "Can not list files with git in {:?}: {}",
+                dir,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        for file in output.stdout.split(|v| *v == 0).filter(|v| !v.is_empty()) {
+            if path.is_file() {
+                ret.insert(path.to_path_buf());
+            } else {
+                ret.insert(path.join(String::from_utf8_lossy(file).as_ref()));
+            }
+        }
+    }
+    Ok(ret.into_iter().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn changed_since_args() {
+        let select = GitSelect::ChangedSince(String::from("HEAD~1"));
+
+        assert_eq!(select.args().unwrap().last(), Some(&"HEAD~1"));
+        assert_eq!(GitSelect::Tracked.args().unwrap(), ["ls-files", "-z"]);
+        for rev in ["--output=/tmp/findsource", "-p", ""] {
+            let select = GitSelect::ChangedSince(String::from(rev));
+
+            assert_eq!(
+                select.args().unwrap_err().kind(),
+                std::io::ErrorKind::InvalidInput
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn list_tracked_and_untracked() {
+        let root = std::env::temp_dir().join(format!("findsource-git-{}", std::process::id()));
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=fs", "-c", "user.email=fs@localhost"])
+                .args(args)
+                .current_dir(&root)
+                .output()
+                .unwrap()
+        };
+
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.rs"), "").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "a.rs"]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(root.join("b.rs"), "").unwrap();
+        std::fs::write(root.join("a.rs"), "changed").unwrap();
+
+        assert_eq!(
+            list_files(&root, &[GitSelect::Tracked]).await.unwrap(),
+            [root.join("a.rs")]
+        );
+        assert_eq!(
+            list_files(&root, &[GitSelect::Untracked]).await.unwrap(),
+            [root.join("b.rs")]
+        );
+        assert_eq!(
+            list_files(&root, &[GitSelect::Modified, GitSelect::Untracked])
+                .await
+                .unwrap(),
+            [root.join("a.rs"), root.join("b.rs")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use aopt::error;
+use aopt::Error;
+use aopt::HashMap;
+use sha2::Digest;
+use tokio::task::JoinSet;
+
+/// The digest algorithm used by `--hash`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+
+    Blake3,
+
+    Xxh3,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "blake3" => Ok(Self::Blake3),
+            "xxh3" => Ok(Self::Xxh3),
+            _ => Err(error!(
+                "Unknown hash algorithm `{s}`, expect sha256, blake3 or xxh3"
+            )),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+            Self::Xxh3 => "xxh3",
+        }
+    }
+
+    /// The count of hex digits in the digest.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake3 => 64,
+            Self::Xxh3 => 16,
+        }
+    }
+}
+
+pub fn hash_file(path: &str, alg: HashAlgorithm) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+
+    match alg {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = sha2::Sha256::new();
+
+            std::io::copy(&mut file, &mut hasher)?;
+            Ok(to_hex(&hasher.finalize()))
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+
+            hasher.update_reader(file)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+        HashAlgorithm::Xxh3 => {
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            let mut buf = vec![0; 64 * 1024];
+
+            loop {
+                let count = file.read(&mut buf)?;
+
+                if count == 0 {
+                    break;
+                }
+                hasher.update(&buf[..count]);
+            }
+            Ok(format!("{:016x}", hasher.digest()))
+        }
+    }
+}
+
+/// Hash the files in parallel, the result is sorted by path.
+pub async fn hash_files(
+    files: Vec<String>,
+    alg: HashAlgorithm,
+) -> color_eyre::Result<Vec<(String, std::io::Result<String>)>> {
+    let mut workers = JoinSet::new();
+    let mut ret = vec![];
+
+    for path in files {
+        workers.spawn_blocking(move || {
+            let hash = hash_file(&path, alg);
+
+            (path, hash)
+        });
+    }
+    while let Some(hashed) = workers.join_next().await {
+        ret.push(hashed?);
+    }
+    ret.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(ret)
+}
+
+/// Parse the manifest in the format of `sha256sum`: `hash  path` for every line.
+pub fn parse_manifest(content: &str) -> Result<HashMap<String, String>, Error> {
+    let mut ret = HashMap::default();
+
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (hash, path) = line
+            .split_once("  ")
+            .or_else(|| line.split_once(" *"))
+            .ok_or_else(|| error!("Invalid manifest line {}: `{}`", idx + 1, line))?;
+
+        ret.insert(path.to_owned(), hash.to_owned());
+    }
+    Ok(ret)
+}
+
+/// Check the digests in manifest have the length of `alg`, or infer the algorithm from them if not given.
+/// The sha256 and blake3 digests have the same length, they are told apart by hashing a file in manifest.
+pub fn manifest_algorithm(
+    manifest: &HashMap<String, String>,
+    alg: Option<HashAlgorithm>,
+) -> Result<HashAlgorithm, Error> {
+    let alg = alg.unwrap_or_else(|| match manifest.values().next().map(String::len) {
+        Some(16) => HashAlgorithm::Xxh3,
+        _ => {
+            let mut files: Vec<_> = manifest.iter().collect();
+
+            files.sort();
+            files
+                .into_iter()
+                .find_map(|(path, hash)| {
+                    hash_file(path, HashAlgorithm::Blake3)
+                        .ok()
+                        .map(|v| (v, hash))
+                })
+                .filter(|(v, hash)| v.eq_ignore_ascii_case(hash))
+                .map(|_| HashAlgorithm::Blake3)
+                .unwrap_or_default()
+        }
+    });
+
+    match manifest
+        .iter()
+        .find(|(_, hash)| hash.len() != alg.digest_len())
+    {
+        Some((path, hash)) => Err(error!(
+            "The digest `{}` of `{}` in manifest is not a {} digest",
+            hash,
+            path,
+            alg.name()
+        )),
+        None => Ok(alg),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|v| format!("{v:02x}")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_sha256sum_manifest() {
+        let manifest = parse_manifest("abc  src/a b.rs\n\ndef *b.rs\n").unwrap();
+
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest["src/a b.rs"], "abc");
+        assert_eq!(manifest["b.rs"], "def");
+    }
+
+    #[test]
+    fn parse_invalid_manifest() {
+        let error = parse_manifest("abc  a.rs\nnohash\n").unwrap_err();
+
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn infer_manifest_algorithm() {
+        let path = std::env::temp_dir().join(format!("findsource-hash-{}", std::process::id()));
+
+        std::fs::write(&path, "findsource").unwrap();
+        let file = path.to_str().unwrap().to_owned();
+        let manifest = |alg| HashMap::from_iter([(file.clone(), hash_file(&file, alg).unwrap())]);
+
+        for alg in [
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Blake3,
+            HashAlgorithm::Xxh3,
+        ] {
+            assert_eq!(manifest_algorithm(&manifest(alg), None).unwrap(), alg);
+        }
+        assert!(
+            manifest_algorithm(&manifest(HashAlgorithm::Xxh3), Some(HashAlgorithm::Sha256))
+                .is_err()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
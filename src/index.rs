@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::check_symlink_loop;
+
+const INDEX_VERSION: u32 = 2;
+
+/// A file recorded in the index.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IndexFile {
+    pub name: String,
+
+    pub size: u64,
+
+    pub mtime: u64,
+}
+
+/// A directory recorded in the index, it will be re-walked if the mtime changed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IndexDir {
+    pub mtime: u64,
+
+    pub dirs: Vec<String>,
+
+    pub files: Vec<IndexFile>,
+}
+
+/// Persistent index of every file under a root, the key of `dirs` is relative to the root.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+
+    /// Are the hidden directories walked.
+    hidden: bool,
+
+    dirs: BTreeMap<PathBuf, IndexDir>,
+}
+
+impl Index {
+    /// The index file of root in the cache directory, it is keyed by the canonical path of root.
+    pub fn path(root: &Path, hidden: bool) -> Option<PathBuf> {
+        let root = dunce::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let name = root.file_name().and_then(|v| v.to_str()).unwrap_or("root");
+        let suffix = if hidden { "-hidden" } else { "" };
+
+        cache_directory().map(|dir| {
+            dir.join(format!(
+                "{name}-{:016x}{suffix}.index",
+                fnv1a(root.as_os_str().as_encoded_bytes())
+            ))
+        })
+    }
+
+    /// Load the index of root, return None if it is not exist, broken or the version mismatched.
+    pub fn load(root: &Path, hidden: bool) -> std::io::Result<Option<Self>> {
+        let Some(path) = Self::path(root, hidden).filter(|v| v.is_file()) else {
+            return Ok(None);
+        };
+        let index: Option<Self> = bincode::deserialize_from(BufReader::new(File::open(path)?)).ok();
+
+        Ok(index.filter(|v| v.version == INDEX_VERSION && v.hidden == hidden))
+    }
+
+    /// Save the index into the cache directory, it is written to a temporary file first.
+    pub fn save(&self, root: &Path) -> std::io::Result<()> {
+        let path = Self::path(root, self.hidden).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Can not find the cache directory, set XDG_CACHE_HOME or HOME",
+            )
+        })?;
+        let temp = path.with_extension(format!("index.{}", std::process::id()));
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let writer = BufWriter::new(File::create(&temp)?);
+
+        bincode::serialize_into(writer, self).map_err(std::io::Error::other)?;
+        std::fs::rename(&temp, &path)
+    }
+
+    /// Walk the whole tree of root, the hidden directories are skipped if `hidden` is false.
+    pub fn build(root: &Path, hidden: bool) -> std::io::Result<Self> {
+        let mut index = Self {
+            version: INDEX_VERSION,
+            hidden,
+            ..Default::default()
+        };
+
+        index.update(root)?;
+        Ok(index)
+    }
+
+    /// Re-walk the directories whose mtime changed, return the count of them.
+    pub fn update(&mut self, root: &Path) -> std::io::Result<usize> {
+        let mut olds = std::mem::take(&mut self.dirs);
+        let mut pendings = vec![PathBuf::new()];
+        let mut count = 0;
+
+        while let Some(rel) = pendings.pop() {
+            let dir = root.join(&rel);
+            let Ok(meta) = std::fs::metadata(&dir) else {
+                continue;
+            };
+            let mtime = mtime_of(&meta);
+            let entry = match olds.remove(&rel) {
+                Some(entry) if entry.mtime == mtime => entry,
+                _ => {
+                    count += 1;
+                    match read_index_dir(&dir, mtime, self.hidden) {
+                        Ok(entry) => entry,
+                        Err(_) => continue,
+                    }
+                }
+            };
+
+            pendings.extend(entry.dirs.iter().map(|v| rel.join(v)));
+            self.dirs.insert(rel, entry);
+        }
+        Ok(count)
+    }
+
+    /// Load the index and update it, or build a new one; the index is saved if anything changed.
+    /// The error of saving is returned with the index, which is usable anyway.
+    pub fn refresh(
+        root: &Path,
+        hidden: bool,
+    ) -> std::io::Result<(Self, usize, Option<std::io::Error>)> {
+        let (index, count) = match Self::load(root, hidden)? {
+            Some(mut index) => {
+                let count = index.update(root)?;
+
+                (index, count)
+            }
+            None => {
+                let index = Self::build(root, hidden)?;
+                let count = index.dirs.len();
+
+                (index, count)
+            }
+        };
+        let error = if count > 0 {
+            index.save(root).err()
+        } else {
+            None
+        };
+
+        Ok((index, count, error))
+    }
+
+    /// Iterate the files, with the directory path relative to root.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &IndexFile)> {
+        self.dirs
+            .iter()
+            .flat_map(|(rel, dir)| dir.files.iter().map(move |file| (rel.as_path(), file)))
+    }
+
+    pub fn dir_count(&self) -> usize {
+        self.dirs.len()
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.dirs.values().map(|v| v.files.len()).sum()
+    }
+}
+
+fn read_index_dir(dir: &Path, mtime: u64, hidden: bool) -> std::io::Result<IndexDir> {
+    let mut ret = IndexDir {
+        mtime,
+        ..Default::default()
+    };
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+        let path = entry.path();
+        // follow the symbol link like the finder
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+
+        if meta.is_dir() {
+            // such as `.git`
+            if (hidden || !name.starts_with('.')) && check_symlink_loop(&path).is_ok() {
+                ret.dirs.push(name);
+            }
+        } else if meta.is_file() {
+            ret.files.push(IndexFile {
+                name,
+                size: meta.len(),
+                mtime: mtime_of(&meta),
+            });
+        }
+    }
+    Ok(ret)
+}
+
+fn mtime_of(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+        .map(|v| v.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// The directory of index files, `$XDG_CACHE_HOME/findsource` or `~/.cache/findsource`.
+pub fn cache_directory() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .filter(|v| !v.is_empty())
+                .map(|v| PathBuf::from(v).join(".cache"))
+        })
+        .map(|v| v.join("findsource"))
+}
+
+/// The FNV-1a hash, it is stable between the builds unlike the hasher of std.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("findsource-{}-{}", name, std::process::id()));
+
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("a.rs"), "").unwrap();
+        std::fs::write(root.join("sub").join("b.rs"), "").unwrap();
+        std::fs::write(root.join(".git").join("c.rs"), "").unwrap();
+        root
+    }
+
+    fn file_paths(index: &Index) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = index
+            .files()
+            .map(|(rel, file)| rel.join(&file.name))
+            .collect();
+
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn build_skip_hidden() {
+        let root = temp_root("index-hidden");
+        let visible = Index::build(&root, false).unwrap();
+        let hidden = Index::build(&root, true).unwrap();
+
+        assert_eq!(
+            file_paths(&visible),
+            [Path::new("a.rs"), Path::new("sub/b.rs")]
+        );
+        assert_eq!(hidden.file_count(), 3);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_follow_symlink() {
+        let root = temp_root("index-symlink");
+
+        std::os::unix::fs::symlink(root.join("sub"), root.join("link")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("sub").join("loop")).unwrap();
+        let index = Index::build(&root, false).unwrap();
+
+        assert_eq!(
+            file_paths(&index),
+            [
+                Path::new("a.rs"),
+                Path::new("link/b.rs"),
+                Path::new("sub/b.rs")
+            ]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn refresh_changed_directory() {
+        let root = temp_root("index-refresh");
+
+        std::env::set_var(
+            "XDG_CACHE_HOME",
+            std::env::temp_dir().join("findsource-cache"),
+        );
+        let (_, count, error) = Index::refresh(&root, false).unwrap();
+
+        assert_eq!((count, error.is_none()), (2, true));
+        let (_, count, _) = Index::refresh(&root, false).unwrap();
+
+        assert_eq!(count, 0);
+        std::fs::write(root.join("sub").join("d.rs"), "").unwrap();
+        let (index, count, _) = Index::refresh(&root, false).unwrap();
+
+        assert_eq!(count, 1);
+        assert!(file_paths(&index).contains(&PathBuf::from("sub/d.rs")));
+        std::fs::remove_file(Index::path(&root, false).unwrap()).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,11 +1,32 @@
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+/// The ids of built-in options can be set by the project configuration, they only change the matching
+/// and the format of output. Others such as `--tar` or `--git-changed-since` write files or run commands.
+pub const PROJECT_OPTIONS: &[&str] = &[
+    "whole", "Whole", "ext", "Ext", "X", "ignore", "only", "reverse", "hidden", "full", "inv",
+    "max", "format",
+];
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct JsonOptCollection {
+    /// The configurations merged before this one, found by name or path like `-l`.
+    #[serde(default, alias = "include", skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
+    /// The option values set before the command line, such as `"--hidden": true`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, JsonDefault>,
+
+    /// The named arguments, expanded by `@name` or `-p name`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Vec<String>>,
+
+    #[serde(default)]
     pub opts: Vec<JsonConfig>,
 }
 
@@ -26,11 +47,131 @@ impl JsonOptCollection {
     pub fn append_opts(self, parser: &mut AFwdParser) -> Result<(), Error> {
         for meta in self.opts.into_iter() {
             let cfg: OptConfig = meta.build(parser.optset_mut())?;
-            parser.add_opt_cfg(cfg)?;
+            // commit the option now, the error is a panic when dropping the commit
+            parser.add_opt_cfg(cfg)?.run()?;
         }
         Ok(())
     }
 
+    /// Merge the options, defaults and aliases of other configuration.
+    pub fn merge(&mut self, other: JsonOptCollection) -> &mut Self {
+        self.add_defaults(other.defaults);
+        self.add_aliases(other.aliases);
+        for cfg in other.opts {
+            self.add_json_config(cfg);
+        }
+        self
+    }
+
+    /// Merge the defaults, the later one overrides the same option.
+    pub fn add_defaults(&mut self, defaults: BTreeMap<String, JsonDefault>) -> &mut Self {
+        self.defaults.extend(defaults);
+        self
+    }
+
+    /// Merge the aliases, the later one overrides the same name.
+    pub fn add_aliases(&mut self, aliases: BTreeMap<String, Vec<String>>) -> &mut Self {
+        self.aliases.extend(aliases);
+        self
+    }
+
+    /// Expand the alias, the arguments in alias can refer to other aliases by `@name`.
+    pub fn expand_alias(&self, name: &str, stack: &mut Vec<String>) -> Result<Vec<String>, Error> {
+        if stack.iter().any(|v| v == name) {
+            return Err(aopt::error!(
+                "Cyclic alias: {} -> {}",
+                stack.join(" -> "),
+                name
+            ));
+        }
+        let args = self
+            .aliases
+            .get(name)
+            .ok_or_else(|| aopt::error!("Can not find alias `{}` in configuration", name))?;
+        let mut ret = vec![];
+
+        stack.push(name.to_owned());
+        for arg in args {
+            match arg.strip_prefix('@') {
+                Some(name) => ret.extend(self.expand_alias(name, stack)?),
+                None => ret.push(arg.clone()),
+            }
+        }
+        stack.pop();
+        Ok(ret)
+    }
+
+    /// The arguments of defaults, they are inserted before the command line arguments.
+    /// The default is skipped if the option or its alias is in `given`.
+    pub fn default_args(&self, given: &[&str]) -> Vec<String> {
+        self.defaults
+            .iter()
+            .filter(|(name, _)| {
+                let names = self
+                    .opts
+                    .iter()
+                    .map(|v| v.names())
+                    .find(|v| v.contains(&name.as_str()))
+                    .unwrap_or_else(|| vec![name.as_str()]);
+
+                !names.iter().any(|v| given.contains(v))
+            })
+            .flat_map(|(name, value)| value.to_args(name))
+            .collect()
+    }
+
+    /// Return true if the project configuration can set the option with given name, it must be
+    /// a category or in [`PROJECT_OPTIONS`]. The `builtin` is the default configuration.
+    pub fn is_project_option(&self, name: &str, builtin: &JsonOptCollection) -> bool {
+        self.opts
+            .iter()
+            .filter(|v| v.names().contains(&name))
+            .all(|v| {
+                PROJECT_OPTIONS.contains(&v.id.as_str()) || builtin.iter().all(|b| b.id != v.id)
+            })
+    }
+
+    /// Remove the options of project configuration that change the built-in options not allowed,
+    /// return the ids of them. The `builtin` is the default configuration.
+    pub fn restrict_project(&mut self, builtin: &JsonOptCollection) -> Vec<String> {
+        let mut removed = vec![];
+
+        self.opts.retain(|cfg| {
+            let allowed = (PROJECT_OPTIONS.contains(&cfg.id.as_str())
+                || builtin.iter().all(|v| v.id != cfg.id))
+                && cfg
+                    .names()
+                    .iter()
+                    .all(|v| builtin.is_project_option(v, builtin));
+
+            if !allowed {
+                removed.push(cfg.id.clone());
+            }
+            allowed
+        });
+        removed
+    }
+
+    /// Remove the defaults with given names that set the options not allowed in project configuration,
+    /// or match no option, return the names of them. It is called on the merged configuration.
+    pub fn restrict_project_defaults(
+        &mut self,
+        names: &[String],
+        builtin: &JsonOptCollection,
+    ) -> Vec<String> {
+        let removed: Vec<_> = names
+            .iter()
+            .filter(|name| {
+                !self.opts.iter().any(|v| v.names().contains(&name.as_str()))
+                    || !self.is_project_option(name, builtin)
+            })
+            .cloned()
+            .collect();
+
+        self.defaults.retain(|name, _| !removed.contains(name));
+        removed
+    }
+
     pub fn add_json_config(&mut self, mut cfg: JsonConfig) -> &mut Self {
         let config = self.opts.iter_mut().find(|v| v.id == cfg.id);
 
@@ -51,6 +192,9 @@ impl JsonOptCollection {
                 if config.alias.is_none() {
                     config.set_alias(cfg.take_alias());
                 }
+                if config.comment.is_none() {
+                    config.set_comment(cfg.take_comment());
+                }
                 config.merge_value(&mut cfg);
             }
             None => {
@@ -81,9 +225,58 @@ pub struct JsonConfig {
     pub alias: Option<Vec<String>>,
 
     pub value: Option<Vec<String>>,
+
+    pub comment: Option<JsonComment>,
+}
+
+/// The value of option in `defaults` of configuration.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum JsonDefault {
+    /// Enable the boolean option if true.
+    Flag(bool),
+
+    Number(i64),
+
+    Value(String),
+
+    /// Set the option multiple times, such as `--only`.
+    Values(Vec<String>),
+}
+
+impl JsonDefault {
+    pub fn to_args(&self, name: &str) -> Vec<String> {
+        match self {
+            Self::Flag(true) => vec![name.to_owned()],
+            Self::Flag(false) => vec![],
+            Self::Number(value) => vec![format!("{name}={value}")],
+            Self::Value(value) => vec![format!("{name}={value}")],
+            Self::Values(values) => values.iter().map(|v| format!("{name}={v}")).collect(),
+        }
+    }
+}
+
+/// Hold the comment syntax of a category, used when counting lines.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct JsonComment {
+    #[serde(default)]
+    pub line: Vec<String>,
+
+    #[serde(default)]
+    pub block: Vec<(String, String)>,
 }
 
 impl JsonConfig {
+    /// The names of option, include the aliases.
+    pub fn names(&self) -> Vec<&str> {
+        let option = self.option.split('=').next().unwrap_or_default();
+
+        option
+            .split(';')
+            .chain(self.alias.iter().flatten().map(String::as_str))
+            .collect()
+    }
+
     pub fn take_option(&mut self) -> String {
         std::mem::take(&mut self.option)
     }
@@ -108,6 +301,10 @@ impl JsonConfig {
         self.value.take()
     }
 
+    pub fn take_comment(&mut self) -> Option<JsonComment> {
+        self.comment.take()
+    }
+
     pub fn set_id(&mut self, id: impl Into<String>) -> &mut Self {
         self.id = id.into();
         self
@@ -143,6 +340,11 @@ impl JsonConfig {
         self
     }
 
+    pub fn set_comment(&mut self, comment: Option<JsonComment>) -> &mut Self {
+        self.comment = comment;
+        self
+    }
+
     pub fn merge_value(&mut self, other: &mut Self) -> &mut Self {
         match self.value.as_mut() {
             Some(value) => {
@@ -189,3 +391,105 @@ where
         Ok(cfg)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn collection(json: &str) -> JsonOptCollection {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn restrict_project_options() {
+        let builtin = collection(crate::config::default_json_configuration());
+        let mut project = collection(
+            r#"{
+                "opts": [
+                    { "id": "tar", "option": "--tar=s", "value": ["out.tar"] },
+                    { "id": "copy", "option": "--copy-dir=s", "alias": ["--copy-to"] },
+                    { "id": "ext", "option": "-e=s", "value": ["rs"] },
+                    { "id": "proto", "option": "-proto=s", "value": ["proto"] }
+                ]
+            }"#,
+        );
+
+        assert_eq!(project.restrict_project(&builtin), ["tar", "copy"]);
+        assert_eq!(project.len(), 2);
+        assert!(builtin.is_project_option("--hidden", &builtin));
+        assert!(builtin.is_project_option("-proto", &builtin));
+        assert!(!builtin.is_project_option("--git-changed-since", &builtin));
+    }
+
+    #[test]
+    fn restrict_project_defaults() {
+        let builtin = collection(crate::config::default_json_configuration());
+        let mut merged = builtin.clone();
+
+        merged.add_json_config(
+            collection(r#"{ "opts": [{ "id": "tar", "option": "--tar=s", "alias": ["-T"] }] }"#)
+                .opts
+                .remove(0),
+        );
+        merged.add_defaults(
+            collection(
+                r#"{
+                    "defaults": {
+                        "--hidden": true,
+                        "--tar": "out.tar",
+                        "-T": "out.tar",
+                        "--git-changed-since": "--output=pwned",
+                        "--unknown": true,
+                        "--max-results": 1
+                    }
+                }"#,
+            )
+            .defaults,
+        );
+        let names: Vec<_> = merged.defaults.keys().cloned().collect();
+        let mut removed = merged.restrict_project_defaults(&names, &builtin);
+
+        removed.sort();
+        assert_eq!(removed, ["--git-changed-since", "--tar", "--unknown", "-T"]);
+        assert_eq!(merged.defaults.len(), 2);
+    }
+
+    #[test]
+    fn expand_nested_alias() {
+        let config =
+            collection(r#"{ "aliases": { "a": ["-c", "@b"], "b": ["-cpp", "@c"], "c": ["-h"] } }"#);
+
+        assert_eq!(
+            config.expand_alias("a", &mut vec![]).unwrap(),
+            ["-c", "-cpp", "-h"]
+        );
+        assert!(config.expand_alias("d", &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn expand_cyclic_alias() {
+        let config = collection(r#"{ "aliases": { "a": ["-c", "@b"], "b": ["@a"] } }"#);
+        let error = config.expand_alias("a", &mut vec![]).unwrap_err();
+
+        assert!(error.to_string().contains("Cyclic alias: a -> b -> a"));
+    }
+
+    #[test]
+    fn override_default_args() {
+        let config = collection(
+            r#"{
+                "defaults": { "--hidden": true, "--max-results": 10, "--only": ["rust", "c"] },
+                "opts": [{ "id": "hidden", "option": "--hidden=b", "alias": ["-a"] }]
+            }"#,
+        );
+
+        assert_eq!(
+            config.default_args(&["-a"]),
+            ["--max-results=10", "--only=rust", "--only=c"]
+        );
+        assert_eq!(
+            config.default_args(&["--only"]),
+            ["--hidden", "--max-results=10"]
+        );
+    }
+}
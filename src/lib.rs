@@ -0,0 +1,30 @@
+//! Search the source files by extension or whole name, the `fs` command line tool is built on it.
+//!
+//! Use [`FinderBuilder`] to embed the finder in other programs.
+
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod builder;
+pub mod cancel;
+#[cfg(all(unix, feature = "daemon"))]
+pub mod daemon;
+pub mod error;
+pub mod finder;
+pub mod git;
+#[cfg(feature = "index")]
+pub mod index;
+mod r#macro;
+pub mod matcher;
+pub mod sink;
+pub mod walk;
+
+pub use builder::FinderBuilder;
+pub use cancel::CancelToken;
+pub use error::FindError;
+pub use finder::Filter;
+pub use finder::Finder;
+pub use finder::MatchStream;
+pub use finder::Matched;
+pub use matcher::Matcher;
+pub use sink::Sink;
+pub use walk::Walk;
@@ -1,12 +1,19 @@
+mod bundle;
+mod check;
 mod config;
-mod finder;
-mod r#macro;
+mod duplicate;
+mod format;
+mod hash;
+mod report;
+mod show;
+mod stats;
+mod watch;
 
 pub mod json;
 
 use std::borrow::Cow;
+use std::ffi::OsString;
 use std::path::PathBuf;
-use std::sync::Arc;
 
 use aopt::prelude::*;
 use aopt::shell::shell::Complete;
@@ -18,16 +25,53 @@ use aopt::Error;
 use aopt::HashMap;
 use color_eyre::Result;
 use config::default_json_configuration;
+use config::find_project_configuration;
 use config::get_configuration_directories;
+use config::load_configuration_chain;
 use config::try_to_load_configuration2;
-use finder::Finder;
+use config::ConfigFormat;
+use findsource::finder::Finder;
+use findsource::git::GitSelect;
+use findsource::index::Index;
+use findsource::FindError;
+use findsource::FinderBuilder;
+use findsource::Matched;
+use json::JsonComment;
 use json::JsonOptCollection;
+use report::Report;
 use tokio::fs::read_dir;
 use tokio::io::AsyncWriteExt;
 use tokio::runtime::Handle;
 use tokio::sync::mpsc::channel;
 use tokio::sync::mpsc::Receiver;
 
+pub(crate) use findsource::note;
+pub(crate) use r#macro::say;
+
+/// The macros only used by the command line tool.
+mod r#macro {
+    /// Print the message to stdout and flush it, exit the process silently if the reader went away.
+    macro_rules! say {
+        ($fmt:literal) => {
+            $crate::r#macro::say!($fmt,)
+        };
+        ($fmt:literal, $($code:tt)*) => {{
+            let mut stdout = tokio::io::stdout();
+            let ret = match stdout.write_all(&format!(concat!($fmt, "\n"), $($code)*).as_bytes()).await {
+                Ok(_) => stdout.flush().await,
+                Err(e) => Err(e),
+            };
+
+            match ret {
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => std::process::exit(0),
+                ret => ret?,
+            }
+        }};
+    }
+
+    pub(crate) use say;
+}
+
 pub const BIN: &str = "fs";
 
 #[tokio::main]
@@ -41,38 +85,227 @@ async fn main() -> Result<()> {
             cli.try_auto_complete(cc).await?;
         }
     } else {
-        let cli = Cli::new(Args::from_env(), true).await?;
+        let mut args = Args::from_env();
+
+        if args.get(1).is_some_and(|v| v == "index") {
+            match index_command(args.into()).await? {
+                Some(query_args) => args = query_args,
+                None => return Ok(()),
+            }
+        } else if args.get(1).is_some_and(|v| v == "daemon") {
+            return daemon_command(args.into()).await;
+        } else if args.get(1).is_some_and(|v| v == "config") {
+            return config_command(args.into()).await;
+        }
+        let mut cli = Cli::new(args, true).await?;
+        let comments = cli.take_comments();
 
-        if let Some((paths, finder, mut rx)) = cli.into_finder().await? {
+        if let Some((paths, finder, report, rx)) = cli.into_finder().await? {
             if finder.is_empty() {
                 say!("What extension or filename do you want search, try command: fs -? or fs --help",);
                 return Ok(());
             }
-            let debug = finder.debug;
-            let finder = Arc::new(finder);
-
-            for path in paths {
-                let inner_finder = Arc::clone(&finder);
-
-                tokio::spawn(start_worker!(
-                    inner_finder,
-                    path,
-                    Finder::find_in_directory_first,
-                    "ERROR: Can not find file in directory `{:?}`: {:?}"
-                ));
-            }
-            drop(finder);
-            while let Some(file) = rx.recv().await {
-                say!("{}", file);
-            }
+            let debug = report.debug;
+            let quiet = report.quiet;
+
+            finder.search(paths);
+            let matched = report.process(rx, comments).await?;
+
             if debug {
                 note!("INFO: ... Searching end");
             }
+            if quiet && !matched {
+                std::process::exit(1);
+            }
         }
     }
     Ok(())
 }
 
+/// Handle `fs index build|update|query`, return the arguments of searching for `query`.
+/// The `build` and `update` walk the hidden directories if `-a` or `--hidden` is given.
+async fn index_command(args: Vec<OsString>) -> Result<Option<Args>> {
+    let action = args.get(2).and_then(|v| v.to_str()).unwrap_or_default();
+    let mut rest: Vec<OsString> = args.iter().skip(3).cloned().collect();
+
+    match action {
+        "build" | "update" => {
+            let hidden = rest.iter().any(|v| v == "-a" || v == "--hidden");
+
+            rest.retain(|v| v != "-a" && v != "--hidden");
+            if rest.is_empty() {
+                rest.push(OsString::from("."));
+            }
+            for root in rest.into_iter().map(PathBuf::from) {
+                let build = action == "build";
+                let index_root = root.clone();
+                let (index, count) = tokio::task::spawn_blocking(move || {
+                    if build {
+                        let index = Index::build(&index_root, hidden)?;
+                        let count = index.dir_count();
+
+                        index.save(&index_root)?;
+                        Ok((index, count))
+                    } else {
+                        match Index::refresh(&index_root, hidden)? {
+                            (_, _, Some(e)) => Err(e),
+                            (index, count, None) => Ok((index, count)),
+                        }
+                    }
+                })
+                .await?
+                .map_err(|e: std::io::Error| {
+                    color_eyre::eyre::eyre!("Can not index directory {:?}: {:?}", root, e)
+                })?;
+
+                say!(
+                    "{}: {} files, {} directories, {} directories walked",
+                    root.display(),
+                    index.file_count(),
+                    index.dir_count(),
+                    count
+                );
+            }
+            Ok(None)
+        }
+        "query" => {
+            let mut query_args = vec![args[0].clone()];
+
+            query_args.append(&mut rest);
+            query_args.push(OsString::from("--index"));
+            Ok(Some(Args::from(query_args)))
+        }
+        _ => Err(color_eyre::eyre::eyre!(
+            "Unknown index command `{}`, expect build, update or query",
+            action
+        )),
+    }
+}
+
+/// Handle `fs config check|show`, `check [FILE|NAME]...` lints the configurations loaded together like `-l`,
+/// or the project configuration if no name given; `show [-l NAME]...` prints the merged configuration
+/// with the file of each value.
+async fn config_command(args: Vec<OsString>) -> Result<()> {
+    let action = args.get(2).and_then(|v| v.to_str()).unwrap_or_default();
+    let mut names: Vec<String> = args
+        .iter()
+        .skip(3)
+        .filter_map(|v| v.to_str())
+        .map(String::from)
+        .collect();
+    let config_dir = get_configuration_directories();
+    let default = ConfigFormat::Json
+        .parse(default_json_configuration())
+        .unwrap();
+
+    match action {
+        "check" => {
+            if names.is_empty() {
+                let project = std::env::current_dir()
+                    .ok()
+                    .and_then(|v| find_project_configuration(&v));
+
+                names.extend(project.and_then(|v| v.to_str().map(String::from)));
+            }
+            let mut chain = vec![];
+            let mut problems = vec![];
+
+            // keep checking the others if a configuration can not be loaded
+            for name in names {
+                match load_configuration_chain(&config_dir, &name) {
+                    Ok(configs) => chain.extend(configs),
+                    Err(e) => problems.push(check::Problem::load_error(&name, e)),
+                }
+            }
+            problems.extend(check::check(&default, &chain));
+
+            for problem in problems.iter() {
+                say!("{}", problem);
+            }
+            let errors = problems.iter().filter(|v| v.error).count();
+
+            say!(
+                "{} error(s), {} warning(s) found",
+                errors,
+                problems.len() - errors
+            );
+            if errors > 0 {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        "show" => {
+            let mut project = true;
+            let mut loads = vec![];
+            let mut iter = names.into_iter();
+
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--/project-config" => project = false,
+                    "-l" | "--load" => loads.push(iter.next().ok_or_else(|| {
+                        color_eyre::eyre::eyre!("Missing configuration name after `{}`", arg)
+                    })?),
+                    _ => match arg.strip_prefix("--load=") {
+                        Some(name) => loads.push(name.to_owned()),
+                        None => {
+                            return Err(color_eyre::eyre::eyre!(
+                                "Unknown argument `{}`, expect -l NAME or --/project-config",
+                                arg
+                            ))
+                        }
+                    },
+                }
+            }
+            let mut chain = vec![];
+
+            // same order as `Cli::new`: the project configuration, then every `-l`
+            if project {
+                let project = std::env::current_dir()
+                    .ok()
+                    .and_then(|v| find_project_configuration(&v));
+
+                if let Some(path) = project.as_ref().and_then(|v| v.to_str()) {
+                    chain.extend(load_configuration_chain(&config_dir, path)?);
+                }
+            }
+            let project = chain.len();
+
+            for name in loads {
+                chain.extend(load_configuration_chain(&config_dir, &name)?);
+            }
+            say!("{}", show::display(&default, &chain, project).trim_end());
+            Ok(())
+        }
+        _ => Err(color_eyre::eyre::eyre!(
+            "Unknown config command `{}`, expect check or show",
+            action
+        )),
+    }
+}
+
+/// Handle `fs daemon [PATH]...`, serve the queries until interrupted.
+async fn daemon_command(args: Vec<OsString>) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut roots: Vec<PathBuf> = args.iter().skip(2).map(PathBuf::from).collect();
+        let debug = roots.iter().any(|v| v == "-d" || v == "--debug");
+
+        roots.retain(|v| v != "-d" && v != "--debug");
+        if roots.is_empty() {
+            roots.push(PathBuf::from("."));
+        }
+        findsource::daemon::serve(roots, findsource::daemon::socket_path(), debug).await?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = args;
+        Err(color_eyre::eyre::eyre!(
+            "The daemon is only supported on unix platform"
+        ))
+    }
+}
+
 struct Cli<'a> {
     loader: AFwdParser<'a>,
 
@@ -80,25 +313,39 @@ struct Cli<'a> {
 
     args: Args,
 
-    pre_load: HashMap<String, String>,
+    /// The category id and option in the order of configurations, the first category of extension wins.
+    pre_load: Vec<(String, String)>,
+
+    comments: HashMap<String, JsonComment>,
+
+    aliases: Vec<String>,
 }
 
 impl<'a> Cli<'a> {
     pub async fn new(args: Args, allow_debug: bool) -> Result<Cli<'a>> {
         let config_dir = get_configuration_directories();
+        let project_config_dir = config_dir.clone();
         let mut loader = AFwdParser::default();
 
         loader.set_prepolicy(true);
         loader.add_opt("-d;--debug=b: Print debug message")?;
         loader.add_opt("-?;--help=b: Print help message")?;
         loader.add_opt("-v;--verbose=b: Print more debug message")?;
+        loader.add_opt("--/defaults=b: Ignore the defaults set in configuration")?;
+        loader
+            .add_opt("-p;--profile=s: Expand the alias in configuration, same as @NAME")?
+            .set_hint("-p,--profile NAME");
+        loader.add_opt(
+            "--/project-config=b: Disable loading .findsource.json found in current directory or its parents",
+        )?;
         loader
             .add_opt("-l;--load=s: Load option setting from configuration name or file")?
             .set_hint("-l,--load CFG|PATH")
             .set_values_t(Vec::<JsonOptCollection>::new())
             .on(move |set, ctx| {
                 let cfg = ctx.value::<String>()?;
-                let ret = try_to_load_configuration2(&config_dir, &cfg);
+                let ret = try_to_load_configuration2(&config_dir, &cfg)
+                    .map_err(|e| aopt::error!("{}", e));
 
                 if allow_debug {
                     let (path, config) = ret?;
@@ -116,13 +363,57 @@ impl<'a> Cli<'a> {
         let mut ret = loader.parse(args)?;
         let mut debug = *loader.find_val("--debug")?;
         let mut finder = AFwdParser::default();
-        let load_jsons = loader
-            .take_vals::<JsonOptCollection>("--load")
-            .unwrap_or_default();
 
         if !allow_debug {
             debug = false;
         }
+        let mut load_jsons = vec![];
+        let mut project_defaults = vec![];
+        let mut trusted_from = 0;
+        let mut jsonopts = ConfigFormat::Json
+            .parse(default_json_configuration())
+            .unwrap();
+
+        if !*loader.find_val::<bool>("--/project-config")? {
+            // load the project configuration before the configurations given by `-l`
+            let project = std::env::current_dir()
+                .ok()
+                .and_then(|v| find_project_configuration(&v));
+
+            if let Some(path) = project.as_ref().and_then(|v| v.to_str()) {
+                match try_to_load_configuration2(&project_config_dir, path) {
+                    Ok((path, mut config)) => {
+                        if debug {
+                            note!("INFO: ... loading project config {:?}", path);
+                        }
+                        // the project configuration comes with the source, it is not trusted
+                        for id in config.restrict_project(&jsonopts) {
+                            if allow_debug {
+                                note!(
+                                    "WARN: Ignore the option `{}` of project config {:?}, it can not be changed by project",
+                                    id,
+                                    path
+                                );
+                            }
+                        }
+                        project_defaults
+                            .extend(config.defaults.keys().map(|v| (v.clone(), path.clone())));
+                        load_jsons.push(config);
+                        trusted_from = 1;
+                    }
+                    Err(e) => {
+                        if allow_debug {
+                            return Err(e.into());
+                        }
+                    }
+                }
+            }
+        }
+        load_jsons.extend(
+            loader
+                .take_vals::<JsonOptCollection>("--load")
+                .unwrap_or_default(),
+        );
         finder
             .add_opt("path=p@1..: Path need to be search")?
             .set_force(true)
@@ -139,19 +430,37 @@ impl<'a> Cli<'a> {
                     Ok(Some(path))
                 }
             })?;
-        let mut jsonopts: JsonOptCollection =
-            serde_json::from_str(default_json_configuration()).unwrap();
-        let mut pre_load = HashMap::<String, String>::default();
+        let mut pre_load = Vec::<(String, String)>::default();
+        let builtin = jsonopts.clone();
 
         // merge the json configurations
-        load_jsons.into_iter().for_each(|json| {
+        load_jsons.into_iter().enumerate().for_each(|(i, json)| {
+            // the default replaced by `-l` configuration is trusted
+            if i >= trusted_from {
+                project_defaults.retain(|(name, _)| !json.defaults.contains_key(name));
+            }
+            jsonopts.add_defaults(json.defaults);
+            jsonopts.add_aliases(json.aliases);
             for cfg in json.opts {
-                if !pre_load.contains_key(&cfg.id) {
-                    pre_load.insert(cfg.id.clone(), cfg.option.clone());
+                if !pre_load.iter().any(|(id, _)| id == &cfg.id) {
+                    pre_load.push((cfg.id.clone(), cfg.option.clone()));
                 }
                 jsonopts.add_json_config(cfg);
             }
         });
+        let names: Vec<_> = project_defaults.iter().map(|(v, _)| v.clone()).collect();
+
+        for name in jsonopts.restrict_project_defaults(&names, &builtin) {
+            if allow_debug {
+                if let Some((_, path)) = project_defaults.iter().find(|(v, _)| v == &name) {
+                    note!(
+                        "WARN: Ignore the default `{}` of project config {:?}, it can not be changed by project",
+                        name,
+                        path
+                    );
+                }
+            }
+        }
         if debug {
             note!(
                 "INFO: ... loading cfg: {}",
@@ -159,17 +468,74 @@ impl<'a> Cli<'a> {
             );
             note!("INFO: ... loading options: {:?}", pre_load);
         }
+        let comments = jsonopts
+            .iter()
+            .filter_map(|cfg| cfg.comment.clone().map(|v| (cfg.id.clone(), v)))
+            .collect();
+        let mut args = vec![];
+        let mut rest = ret.take_args().into_iter();
+        let profiles = loader.take_vals::<String>("--profile").unwrap_or_default();
+
+        // expand the aliases given by `-p` and `@NAME`, the profiles are placed after program name
+        args.extend(rest.next());
+        for profile in profiles {
+            match jsonopts.expand_alias(&profile, &mut vec![]) {
+                Ok(expanded) => args.extend(expanded.into_iter().map(OsString::from)),
+                Err(e) if allow_debug => return Err(e.into()),
+                Err(_) => {}
+            }
+        }
+        for arg in rest {
+            // the `@NAME` is passed through if it is not an alias, such as `@types` directory
+            let expanded = arg
+                .to_str()
+                .and_then(|v| v.strip_prefix('@'))
+                .filter(|v| jsonopts.aliases.contains_key(*v))
+                .map(|v| jsonopts.expand_alias(v, &mut vec![]));
+
+            match expanded {
+                Some(Ok(expanded)) => args.extend(expanded.into_iter().map(OsString::from)),
+                Some(Err(e)) if allow_debug => return Err(e.into()),
+                _ => args.push(arg),
+            }
+        }
+        let aliases = jsonopts.aliases.keys().cloned().collect();
+        let given: Vec<_> = args
+            .iter()
+            .skip(1)
+            .filter_map(|v| v.to_str())
+            .map(|v| v.split('=').next().unwrap_or_default())
+            .collect();
+        let defaults = if *loader.find_val::<bool>("--/defaults")? {
+            vec![]
+        } else {
+            jsonopts.default_args(&given)
+        };
+
+        if debug && !defaults.is_empty() {
+            note!("INFO: ... using defaults: {:?}", defaults);
+        }
+        // insert the defaults after program name, so the command line can override them
+        let at = args.len().min(1);
+
+        args.splice(at..at, defaults.into_iter().map(OsString::from));
         // add the option to finder
         jsonopts.append_opts(&mut finder)?;
 
         Ok(Self {
             loader,
             finder,
-            args: Args::from(ret.take_args()),
+            args: Args::from(args),
             pre_load,
+            comments,
+            aliases,
         })
     }
 
+    pub fn take_comments(&mut self) -> HashMap<String, JsonComment> {
+        std::mem::take(&mut self.comments)
+    }
+
     pub fn list_configurations<O>(handle: Handle) -> impl Values<O, Err = Error> {
         once_values(move |_| {
             handle.block_on(async move {
@@ -183,9 +549,7 @@ impl<'a> Cli<'a> {
                     if let Ok(mut entrys) = read_dir(&dir).await {
                         while let Ok(entry) = entrys.next_entry().await {
                             if let Some(path) = entry.map(|v| v.path()) {
-                                if path.is_file()
-                                    && Some("json") == path.extension().and_then(|v| v.to_str())
-                                {
+                                if path.is_file() && ConfigFormat::from_path(&path).is_some() {
                                     if let Some(filename) = path.with_extension("").file_name() {
                                         cfgs.push(filename.to_os_string());
                                     }
@@ -210,16 +574,28 @@ impl<'a> Cli<'a> {
             let mut ctx = cli.get_context()?;
             let mut manager = CompletionManager::new(self.finder.optset);
             let cfg_uid = manager.optset().find_uid("-l")?;
+            let profile_uid = manager.optset().find_uid("-p")?;
+            let aliases: Vec<OsString> = self.aliases.iter().map(OsString::from).collect();
 
             shell.set_buff(std::io::stdout());
             manager.set_values(cfg_uid, Self::list_configurations(Handle::current()));
+            manager.set_values(profile_uid, aliases);
             manager.complete(shell, &mut ctx)?;
             Ok(())
         })?;
         Ok(())
     }
 
-    pub async fn into_finder(self) -> Result<Option<(Vec<PathBuf>, Finder, Receiver<String>)>> {
+    pub async fn into_finder(
+        self,
+    ) -> Result<
+        Option<(
+            Vec<PathBuf>,
+            Finder,
+            Report,
+            Receiver<Result<Matched, FindError>>,
+        )>,
+    > {
         let mut loader = self.loader;
         let mut finder = self.finder;
         let pre_load = self.pre_load;
@@ -272,9 +648,122 @@ impl<'a> Cli<'a> {
             note!("INFO: ... Got search path: {:?}", paths);
         }
         let (tx, rx) = channel(512);
-        let finder = Finder::new(pre_load, finder, debug, verbose, tx).await?;
+        let mut report = Report::new(&finder)?;
+        let mut builder = finder_builder(pre_load, &finder)?;
+
+        builder.set_debug(debug).set_verbose(verbose);
+        let finder = builder.build(tx);
+
+        if debug {
+            note!("INFO: match whole filename : {:?}", finder.filter().whos());
+            note!("INFO: match file extension : {:?}", finder.filter().exts());
+        }
+        report.debug = debug;
+        report.token = finder.cancel_token();
+        report.roots = paths.clone();
+        report.filter = finder.filter();
+        report.start_watch()?;
+
+        Ok(Some((paths, finder, report, rx)))
+    }
+}
+
+/// Convert the options of finder to [`FinderBuilder`], `opts` is the category id and option of configuration.
+fn finder_builder(opts: Vec<(String, String)>, parser: &AFwdParser<'_>) -> Result<FinderBuilder> {
+    let mut builder = FinderBuilder::new();
+    let only = parser.find_val::<String>("--only");
+    let exclude = parser.find_vals::<String>("--Exclude");
+    let only_checker = |name1: &str, name2: &str| -> bool {
+        if let Ok(only) = only {
+            only.eq(name1) || only.eq(name2)
+        } else {
+            true
+        }
+    };
+    let exclude_checker = move |name1: &str, name2: &str| -> bool {
+        if let Ok(exclude) = exclude {
+            exclude.iter().any(|v| v.eq(name1) || v.eq(name2))
+        } else {
+            false
+        }
+    };
+
+    if only_checker("whole", "w") && !exclude_checker("whole", "w") {
+        if let Ok(whole) = parser.find_vals::<String>("--whole") {
+            for name in whole {
+                builder.add_whole(name);
+            }
+        }
+    }
+    if only_checker("extension", "e") && !exclude_checker("extension", "e") {
+        if let Ok(extension) = parser.find_vals::<String>("--extension") {
+            for ext in extension {
+                builder.add_extension(ext);
+            }
+        }
+    }
+    for (id, opt) in opts {
+        if only_checker(id.as_str(), "") && !exclude_checker(id.as_str(), "") {
+            if let Ok(opt_exts) = parser.find_vals::<String>(opt.as_str()) {
+                builder.add_category(id, opt_exts);
+            }
+        }
+    }
+    if let Ok(ex_exts) = parser.find_vals::<String>("--Extension") {
+        for ext in ex_exts {
+            builder.exclude_extension(ext);
+        }
+    }
+    if let Ok(ex_whos) = parser.find_vals::<String>("--Whole") {
+        for name in ex_whos {
+            builder.exclude_whole(name);
+        }
+    }
+    builder
+        .set_full(*parser.find_val("--full")?)
+        .set_ignore_case(*parser.find_val("--ignore-case")?)
+        .set_recursive(!*parser.find_val::<bool>("--/reverse")?)
+        .set_hidden(*parser.find_val("--hidden")?)
+        .set_invert(*parser.find_val("--invert")?)
+        .set_archive(*parser.find_val("--search-archives")?)
+        .set_index(*parser.find_val("--index")?)
+        .set_daemon(*parser.find_val("--use-daemon")?);
+    if *parser.find_val("--git-tracked")? {
+        builder.add_git(GitSelect::Tracked);
+    }
+    if *parser.find_val("--git-untracked")? {
+        builder.add_git(GitSelect::Untracked);
+    }
+    if *parser.find_val("--git-modified")? {
+        builder.add_git(GitSelect::Modified);
+    }
+    if let Ok(revs) = parser.find_vals::<String>("--git-changed-since") {
+        for rev in revs {
+            builder.add_git(GitSelect::ChangedSince(rev.clone()));
+        }
+    }
+    Ok(builder)
+}
+
+/// Write to stdout, exit the process silently if the reader went away.
+struct PipeStdout(std::io::Stdout);
+
+impl PipeStdout {
+    fn check<T>(ret: std::io::Result<T>) -> std::io::Result<T> {
+        match ret {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => std::process::exit(0),
+            ret => ret,
+        }
+    }
+}
+
+impl std::io::Write for PipeStdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Self::check(self.0.write(buf))
+    }
 
-        Ok(Some((paths, finder, rx)))
+    fn flush(&mut self) -> std::io::Result<()> {
+        Self::check(self.0.flush())
     }
 }
 
@@ -293,7 +782,7 @@ async fn print_help<'a>(set: &AHCSet<'a>, finder_set: &AHCSet<'a>) -> color_eyre
         &head,
         &foot,
         aopt_help::prelude::Style::default(),
-        std::io::stdout(),
+        PipeStdout(std::io::stdout()),
         40,
         8,
     );
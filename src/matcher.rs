@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::Metadata;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The file checked by [`Matcher`], the metadata is fetched when it is first used.
+#[derive(Debug)]
+pub struct Candidate<'a> {
+    path: &'a Path,
+
+    file_name: &'a str,
+
+    entry: bool,
+
+    metadata: OnceLock<Option<Metadata>>,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn new(path: &'a Path, file_name: &'a str) -> Self {
+        Self {
+            path,
+            file_name,
+            entry: false,
+            metadata: OnceLock::new(),
+        }
+    }
+
+    /// An entry inside the archive, it has no metadata.
+    pub fn entry(path: &'a Path, file_name: &'a str) -> Self {
+        Self {
+            entry: true,
+            ..Self::new(path, file_name)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    pub fn file_name(&self) -> &str {
+        self.file_name
+    }
+
+    pub fn is_entry(&self) -> bool {
+        self.entry
+    }
+
+    /// Get the metadata of file, return None if it can not be accessed or it is an archive entry.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata
+            .get_or_init(|| {
+                if self.entry {
+                    None
+                } else {
+                    std::fs::metadata(self.path).ok()
+                }
+            })
+            .as_ref()
+    }
+}
+
+/// Decide the category of file, return None if the file is not matched.
+pub trait Matcher: Debug + Send + Sync {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str>;
+
+    /// Matched if both matched, the category of `self` is used.
+    fn and<M: Matcher>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Try `self` first, then the `other`.
+    fn or<M: Matcher>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Matched with `category` if `self` is not matched.
+    fn not(self, category: impl Into<String>) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not::new(self, category)
+    }
+}
+
+/// Match the file extension, a name starts with `.` has no extension.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExtensionMatcher {
+    exts: HashMap<String, String>,
+
+    igcase: bool,
+}
+
+impl ExtensionMatcher {
+    /// The key of `exts` is extension, the value is category id.
+    pub fn new(exts: HashMap<String, String>, igcase: bool) -> Self {
+        Self { exts, igcase }
+    }
+
+    pub fn exts(&self) -> &HashMap<String, String> {
+        &self.exts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exts.is_empty()
+    }
+}
+
+impl Matcher for ExtensionMatcher {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        let name = file.file_name();
+
+        match name.rfind('.') {
+            None | Some(0) => None,
+            Some(pos) => {
+                let (_, ext) = name.split_at(pos + 1);
+
+                self.exts.get(ext).map(String::as_str).or_else(|| {
+                    if self.igcase {
+                        self.exts.get(&ext.to_lowercase()).map(String::as_str)
+                    } else {
+                        None
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Match the whole file name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WholeMatcher {
+    whos: HashMap<String, String>,
+
+    igcase: bool,
+}
+
+impl WholeMatcher {
+    /// The key of `whos` is file name, the value is category id.
+    pub fn new(whos: HashMap<String, String>, igcase: bool) -> Self {
+        Self { whos, igcase }
+    }
+
+    pub fn whos(&self) -> &HashMap<String, String> {
+        &self.whos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.whos.is_empty()
+    }
+}
+
+impl Matcher for WholeMatcher {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        let name = file.file_name();
+
+        self.whos.get(name).map(String::as_str).or_else(|| {
+            if self.igcase {
+                self.whos.get(&name.to_lowercase()).map(String::as_str)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Match the path with glob pattern, such as `src/**/*.rs`.
+#[derive(Debug, Clone)]
+pub struct GlobMatcher {
+    glob: globset::GlobMatcher,
+
+    category: String,
+}
+
+impl GlobMatcher {
+    pub fn new(pattern: &str, category: impl Into<String>) -> Result<Self, globset::Error> {
+        Ok(Self {
+            glob: globset::Glob::new(pattern)?.compile_matcher(),
+            category: category.into(),
+        })
+    }
+}
+
+impl Matcher for GlobMatcher {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.glob
+            .is_match(file.path())
+            .then_some(self.category.as_str())
+    }
+}
+
+/// Match the path with regular expression.
+#[derive(Debug, Clone)]
+pub struct RegexMatcher {
+    regex: regex::Regex,
+
+    category: String,
+}
+
+impl RegexMatcher {
+    pub fn new(pattern: &str, category: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: regex::Regex::new(pattern)?,
+            category: category.into(),
+        })
+    }
+}
+
+impl Matcher for RegexMatcher {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.regex
+            .is_match(&file.path().to_string_lossy())
+            .then_some(self.category.as_str())
+    }
+}
+
+/// Match the file size in bytes, the archive entries are never matched.
+#[derive(Debug, Clone)]
+pub struct SizeMatcher {
+    min: Option<u64>,
+
+    max: Option<u64>,
+
+    category: String,
+}
+
+impl SizeMatcher {
+    /// Both `min` and `max` are inclusive.
+    pub fn new(min: Option<u64>, max: Option<u64>, category: impl Into<String>) -> Self {
+        Self {
+            min,
+            max,
+            category: category.into(),
+        }
+    }
+}
+
+impl Matcher for SizeMatcher {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        let size = file.metadata()?.len();
+
+        (self.min.is_none_or(|v| size >= v) && self.max.is_none_or(|v| size <= v))
+            .then_some(self.category.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: Matcher, B: Matcher> Matcher for And<A, B> {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.0
+            .category(file)
+            .filter(|_| self.1.category(file).is_some())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: Matcher, B: Matcher> Matcher for Or<A, B> {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        self.0.category(file).or_else(|| self.1.category(file))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Not<M> {
+    matcher: M,
+
+    category: String,
+}
+
+impl<M: Matcher> Not<M> {
+    pub fn new(matcher: M, category: impl Into<String>) -> Self {
+        Self {
+            matcher,
+            category: category.into(),
+        }
+    }
+}
+
+impl<M: Matcher> Matcher for Not<M> {
+    fn category(&self, file: &Candidate<'_>) -> Option<&str> {
+        match self.matcher.category(file) {
+            Some(_) => None,
+            None => Some(self.category.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exts(igcase: bool) -> ExtensionMatcher {
+        ExtensionMatcher::new(
+            HashMap::from([(String::from("rs"), String::from("rust"))]),
+            igcase,
+        )
+    }
+
+    fn whos(igcase: bool) -> WholeMatcher {
+        WholeMatcher::new(
+            HashMap::from([(String::from("makefile"), String::from("make"))]),
+            igcase,
+        )
+    }
+
+    fn category<M: Matcher>(matcher: &M, name: &str) -> Option<String> {
+        matcher
+            .category(&Candidate::new(Path::new(name), name))
+            .map(String::from)
+    }
+
+    #[test]
+    fn extension_ignore_case() {
+        assert_eq!(category(&exts(false), "lib.rs").as_deref(), Some("rust"));
+        assert_eq!(category(&exts(false), "LIB.RS"), None);
+        assert_eq!(category(&exts(true), "LIB.RS").as_deref(), Some("rust"));
+        assert_eq!(category(&exts(true), ".rs"), None);
+        assert_eq!(category(&exts(true), "rs"), None);
+    }
+
+    #[test]
+    fn whole_ignore_case() {
+        assert_eq!(category(&whos(false), "makefile").as_deref(), Some("make"));
+        assert_eq!(category(&whos(false), "Makefile"), None);
+        assert_eq!(category(&whos(true), "Makefile").as_deref(), Some("make"));
+    }
+
+    #[test]
+    fn combinators() {
+        let or = exts(false).or(whos(false));
+
+        assert_eq!(category(&or, "a.rs").as_deref(), Some("rust"));
+        assert_eq!(category(&or, "makefile").as_deref(), Some("make"));
+        assert_eq!(category(&or, "a.c"), None);
+
+        let and = exts(false).and(RegexMatcher::new("^src/", "src").unwrap());
+
+        assert_eq!(category(&and, "src/a.rs").as_deref(), Some("rust"));
+        assert_eq!(category(&and, "tests/a.rs"), None);
+
+        let not = exts(false).not("other");
+
+        assert_eq!(category(&not, "a.rs"), None);
+        assert_eq!(category(&not, "a.c").as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn size_of_archive_entry() {
+        let any = SizeMatcher::new(None, None, "any");
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+
+        assert_eq!(
+            any.category(&Candidate::new(&path, "Cargo.toml")),
+            Some("any")
+        );
+        assert_eq!(
+            any.category(&Candidate::new(Path::new("not-exist"), "not-exist")),
+            None
+        );
+        assert_eq!(any.category(&Candidate::entry(&path, "Cargo.toml")), None);
+        assert_eq!(
+            SizeMatcher::new(Some(u64::MAX), None, "big")
+                .category(&Candidate::new(&path, "Cargo.toml")),
+            None
+        );
+    }
+}
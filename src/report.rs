@@ -0,0 +1,392 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use aopt::prelude::*;
+use aopt::HashMap;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::Receiver;
+
+use crate::bundle;
+use crate::duplicate;
+use crate::duplicate::Duplicates;
+use crate::format::Format;
+use crate::format::Output;
+use crate::hash;
+use crate::hash::HashAlgorithm;
+use crate::json::JsonComment;
+use crate::note;
+use crate::say;
+use crate::stats::Stats;
+use crate::stats::OTHER_CATEGORY;
+use crate::watch::Watch;
+use findsource::finder::Filter;
+use findsource::finder::Matched;
+use findsource::CancelToken;
+use findsource::FindError;
+
+/// Decide what to do with the matched files, default is print them.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub stats: bool,
+
+    pub dups: bool,
+
+    pub format: Format,
+
+    pub output: Output,
+
+    pub hash: Option<HashAlgorithm>,
+
+    pub manifest: Option<PathBuf>,
+
+    pub verify: Option<PathBuf>,
+
+    pub copy_to: Option<PathBuf>,
+
+    pub tar: Option<PathBuf>,
+
+    pub watch: bool,
+
+    pub max_results: Option<u64>,
+
+    pub quiet: bool,
+
+    /// Stop the finder when enough file matched.
+    pub token: CancelToken,
+
+    /// Is any file matched, used by the quiet mode.
+    pub matched: bool,
+
+    pub full: bool,
+
+    pub recursive: bool,
+
+    pub debug: bool,
+
+    /// The search roots, used for keeping the relative tree of matched files.
+    pub roots: Vec<PathBuf>,
+
+    /// The matching rules of finder, used in watch mode.
+    pub filter: Arc<Filter>,
+
+    /// The watcher started before searching, so the changes during searching are not lost.
+    pub watcher: Option<Watch>,
+}
+
+impl Report {
+    pub fn new(parser: &AFwdParser<'_>) -> color_eyre::Result<Self> {
+        let stats = *parser.find_val("--count-lines")?;
+        let dups = *parser.find_val("--duplicates")?;
+        let format = match parser.find_val::<String>("--format") {
+            Ok(format) => format.parse()?,
+            Err(_) => Format::default(),
+        };
+        let output = match parser.find_val::<String>("--output") {
+            Ok(output) => output.parse()?,
+            Err(_) => Output::default(),
+        };
+        let hash = match parser.find_val::<String>("--hash") {
+            Ok(hash) => Some(hash.parse()?),
+            Err(_) => None,
+        };
+        let manifest = parser.find_val::<String>("--hash-manifest").ok();
+        let verify = parser.find_val::<String>("--verify").ok();
+        let copy_to = parser.find_val::<String>("--copy-to").ok();
+        let tar = parser.find_val::<String>("--tar").ok();
+        let watch = *parser.find_val("--watch")?;
+        let max_results = parser.find_val::<u64>("--max-results").ok().copied();
+        let quiet = *parser.find_val("--quiet")?;
+        let full = *parser.find_val("--full")?;
+        let recursive = !*parser.find_val::<bool>("--/reverse")?;
+
+        if max_results == Some(0) {
+            return Err(color_eyre::eyre::eyre!(
+                "`--max-results` must be greater than 0"
+            ));
+        }
+        if watch {
+            // the watch mode prints the changes as lines after listing the matched files
+            let modes = [
+                (stats, "--count-lines"),
+                (dups, "--duplicates"),
+                (hash.is_some(), "--hash"),
+                (manifest.is_some(), "--hash-manifest"),
+                (verify.is_some(), "--verify"),
+                (copy_to.is_some(), "--copy-to"),
+                (tar.is_some(), "--tar"),
+                (quiet, "--quiet"),
+                (output != Output::Lines, "--output"),
+                // the live events are matched against the directory only
+                (*parser.find_val("--search-archives")?, "--search-archives"),
+                (*parser.find_val("--index")?, "--index"),
+                (*parser.find_val("--git-tracked")?, "--git-tracked"),
+                (*parser.find_val("--git-untracked")?, "--git-untracked"),
+                (*parser.find_val("--git-modified")?, "--git-modified"),
+                (
+                    parser
+                        .find_vals::<String>("--git-changed-since")
+                        .is_ok_and(|v| !v.is_empty()),
+                    "--git-changed-since",
+                ),
+            ];
+
+            if let Some((_, name)) = modes.iter().find(|(set, _)| *set) {
+                return Err(color_eyre::eyre::eyre!(
+                    "`--watch` can not be used with `{}`",
+                    name
+                ));
+            }
+        }
+
+        Ok(Self {
+            stats,
+            dups,
+            format,
+            output,
+            hash,
+            manifest: manifest.map(PathBuf::from),
+            verify: verify.map(PathBuf::from),
+            copy_to: copy_to.map(PathBuf::from),
+            tar: tar.map(PathBuf::from),
+            watch,
+            max_results,
+            quiet,
+            token: CancelToken::default(),
+            matched: false,
+            full,
+            recursive,
+            debug: false,
+            roots: vec![],
+            filter: Arc::default(),
+            watcher: None,
+        })
+    }
+
+    /// Start watching the roots if `--watch` set, it must be called before searching.
+    pub fn start_watch(&mut self) -> color_eyre::Result<()> {
+        if self.watch {
+            self.watcher = Some(Watch::new(
+                &self.roots,
+                Arc::clone(&self.filter),
+                self.full,
+                self.recursive,
+                self.debug,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn hash_mode(&self) -> bool {
+        self.hash.is_some() || self.manifest.is_some() || self.verify.is_some()
+    }
+
+    pub fn bundle_mode(&self) -> bool {
+        self.copy_to.is_some() || self.tar.is_some()
+    }
+
+    /// Receive the matched files from finder and output them, return true if any file matched.
+    pub async fn process(
+        mut self,
+        mut rx: Receiver<Result<Matched, FindError>>,
+        comments: HashMap<String, JsonComment>,
+    ) -> color_eyre::Result<bool> {
+        let format = self.format;
+        let hash_mode = self.hash_mode();
+        let bundle_mode = self.bundle_mode();
+        let mut stats = self.stats.then(|| Stats::new(comments));
+        let mut dups = self.dups.then(Duplicates::default);
+        let mut files = vec![];
+        let list_mode = !(self.quiet || self.stats || self.dups || hash_mode || bundle_mode);
+        let mut sink = if list_mode {
+            Some(self.output.sink()?)
+        } else {
+            None
+        };
+        let mut watch = self.watcher.take();
+
+        let mut errors = 0;
+        let mut count = 0;
+
+        while let Some(matched) = rx.recv().await {
+            let matched = match matched {
+                Ok(matched) => matched,
+                Err(e) => {
+                    note!("ERROR: {}", e);
+                    errors += 1;
+                    continue;
+                }
+            };
+
+            count += 1;
+            self.matched = true;
+            if self.quiet {
+                break;
+            }
+            if matched.entry && (self.stats || self.dups || hash_mode || bundle_mode) {
+                note!("WARN: skip the archive entry `{}`", matched.path);
+            } else if let Some(stats) = stats.as_mut() {
+                let category = matched.category.as_deref().unwrap_or(OTHER_CATEGORY);
+
+                if let Err(e) = stats.add_file(category, &matched.path).await {
+                    note!(
+                        "ERROR: Can not count lines of file `{}`: {:?}",
+                        matched.path,
+                        e
+                    );
+                }
+            } else if let Some(dups) = dups.as_mut() {
+                dups.add_file(matched.path);
+            } else if hash_mode || bundle_mode {
+                files.push(matched.path);
+            } else if let Some(sink) = sink.as_mut() {
+                sink.send(&matched)?;
+                // the reader went away, such as `fs | head`
+                if sink.is_closed() {
+                    break;
+                }
+                if let Some(watch) = watch.as_mut() {
+                    watch.insert(matched.path);
+                }
+            }
+            if self.max_results.is_some_and(|v| count >= v) {
+                break;
+            }
+        }
+        // stop the finder if the loop break early
+        self.token.cancel();
+        drop(rx);
+        if let Some(sink) = sink.as_mut() {
+            sink.flush()?;
+        }
+        if self.debug && errors > 0 {
+            note!("INFO: ... {} error(s) occurred while searching", errors);
+        }
+        let matched = self.matched;
+
+        if sink.as_ref().is_some_and(|v| v.is_closed()) {
+            return Ok(matched);
+        }
+        if let Some(stats) = stats {
+            match format {
+                Format::Text => {
+                    say!("{}", stats.display().trim_end());
+                }
+                Format::Json => {
+                    say!("{}", stats.display_json()?);
+                }
+            }
+        } else if let Some(dups) = dups {
+            let groups = dups.find().await?;
+
+            match format {
+                Format::Text => {
+                    if !groups.is_empty() {
+                        say!("{}", duplicate::display(&groups).trim_end());
+                    }
+                }
+                Format::Json => {
+                    say!("{}", serde_json::to_string_pretty(&groups)?);
+                }
+            }
+        } else if hash_mode {
+            self.process_hash(files).await?;
+        } else if bundle_mode {
+            self.process_bundle(files).await?;
+        } else if let Some(watch) = watch {
+            watch.run().await?;
+        }
+        Ok(matched)
+    }
+
+    async fn process_bundle(self, files: Vec<String>) -> color_eyre::Result<()> {
+        let roots = self.roots;
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(PathBuf::from)
+            .map(|path| {
+                let rel = bundle::relative_path(&path, &roots);
+
+                (path, rel)
+            })
+            .collect();
+
+        bundle::check_collision(&mut files)?;
+        if let Some(dir) = self.copy_to {
+            bundle::copy_to(&dir, &files).await?;
+        }
+        if let Some(out) = self.tar {
+            tokio::task::spawn_blocking(move || bundle::write_tar(&out, &files)).await??;
+        }
+        Ok(())
+    }
+
+    async fn process_hash(self, files: Vec<String>) -> color_eyre::Result<()> {
+        if let Some(verify) = self.verify {
+            let content = tokio::fs::read_to_string(&verify).await?;
+            let manifest = hash::parse_manifest(&content)?;
+            let alg = self.hash;
+            let (mut manifest, alg) = tokio::task::spawn_blocking(move || {
+                hash::manifest_algorithm(&manifest, alg).map(|alg| (manifest, alg))
+            })
+            .await??;
+            let hashes = hash::hash_files(files, alg).await?;
+            let mut failed = 0;
+
+            for (path, hash) in hashes {
+                let hash = match hash {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        note!("ERROR: Can not hash file `{}`: {:?}", path, e);
+                        failed += 1;
+                        continue;
+                    }
+                };
+
+                match manifest.remove(&path) {
+                    Some(expect) if expect.eq_ignore_ascii_case(&hash) => {
+                        say!("{}: OK", path);
+                    }
+                    Some(_) => {
+                        say!("{}: FAILED", path);
+                        failed += 1;
+                    }
+                    None => {
+                        say!("{}: NEW", path);
+                        failed += 1;
+                    }
+                }
+            }
+            let mut missing: Vec<_> = manifest.into_keys().collect();
+
+            missing.sort();
+            for path in missing {
+                say!("{}: MISSING", path);
+                failed += 1;
+            }
+            if failed > 0 {
+                say!("{} file(s) did not match manifest {:?}", failed, verify);
+                std::process::exit(1);
+            }
+        } else {
+            let hashes = hash::hash_files(files, self.hash.unwrap_or_default()).await?;
+            let mut lines = String::default();
+
+            for (path, hash) in hashes {
+                match hash {
+                    Ok(hash) => {
+                        lines += &format!("{hash}  {path}\n");
+                    }
+                    Err(e) => {
+                        note!("ERROR: Can not hash file `{}`: {:?}", path, e);
+                    }
+                }
+            }
+            if let Some(manifest) = self.manifest {
+                tokio::fs::write(&manifest, lines).await?;
+            } else if !lines.is_empty() {
+                say!("{}", lines.trim_end());
+            }
+        }
+        Ok(())
+    }
+}
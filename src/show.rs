@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::json::JsonOptCollection;
+
+/// The fields of an option, keyed by the field name.
+fn fields(config: &JsonOptCollection, id: &str) -> serde_json::Map<String, Value> {
+    config
+        .iter()
+        .find(|v| v.id == id)
+        .and_then(|v| serde_json::to_value(v).ok())
+        .and_then(|v| match v {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Merge the default configuration and the chain with `JsonOptCollection::merge` in the order of `Cli::new`,
+/// the first `project` configurations of chain are restricted like the project configuration.
+/// Display the options, defaults and aliases with the file each value came from.
+pub fn display(
+    default: &JsonOptCollection,
+    chain: &[(PathBuf, JsonOptCollection)],
+    project: usize,
+) -> String {
+    let default_path = PathBuf::from("<default>");
+    let mut merged = JsonOptCollection::default();
+    // the file of every field, and of every value appended
+    let mut fields_from = BTreeMap::<(String, String), &Path>::new();
+    let mut values_from = BTreeMap::<String, Vec<&Path>>::new();
+    let mut defaults_from = BTreeMap::<String, &Path>::new();
+    let mut aliases_from = BTreeMap::<String, &Path>::new();
+    let mut project_defaults = vec![];
+    let configs = std::iter::once((default_path.as_path(), default))
+        .chain(chain.iter().map(|(path, config)| (path.as_path(), config)));
+
+    for (index, (path, config)) in configs.enumerate() {
+        let mut config = config.clone();
+
+        if index > 0 && index <= project {
+            config.restrict_project(default);
+            project_defaults.extend(config.defaults.keys().cloned());
+        } else {
+            project_defaults.retain(|v| !config.defaults.contains_key(v));
+        }
+        let before = merged.clone();
+
+        defaults_from.extend(config.defaults.keys().map(|v| (v.clone(), path)));
+        aliases_from.extend(config.aliases.keys().map(|v| (v.clone(), path)));
+        merged.merge(config);
+        for cfg in merged.iter() {
+            let old = fields(&before, &cfg.id);
+
+            for (name, value) in fields(&merged, &cfg.id) {
+                let unset = value.is_null() || value == "";
+
+                if name != "value" && name != "id" && !unset && old.get(&name) != Some(&value) {
+                    fields_from.insert((cfg.id.clone(), name), path);
+                }
+            }
+            let sources = values_from.entry(cfg.id.clone()).or_default();
+            let count = cfg.value.as_ref().map(|v| v.len()).unwrap_or_default();
+
+            sources.resize(count, path);
+        }
+    }
+    for name in merged.restrict_project_defaults(&project_defaults, default) {
+        defaults_from.remove(&name);
+    }
+
+    let mut ret = String::new();
+
+    for cfg in merged.iter() {
+        ret += &format!("{}:\n", cfg.id);
+        for (name, value) in fields(&merged, &cfg.id) {
+            if let Some(path) = fields_from.get(&(cfg.id.clone(), name.clone())) {
+                ret += &format!("    {name} = {value}  # {path:?}\n");
+            }
+        }
+        for (value, path) in cfg.value.iter().flatten().zip(&values_from[&cfg.id]) {
+            ret += &format!("    value = {}  # {path:?}\n", Value::from(value.as_str()));
+        }
+    }
+    for (title, values, sources) in [
+        (
+            "defaults",
+            serde_json::to_value(&merged.defaults),
+            defaults_from,
+        ),
+        (
+            "aliases",
+            serde_json::to_value(&merged.aliases),
+            aliases_from,
+        ),
+    ] {
+        if let Ok(Value::Object(values)) = values {
+            if !values.is_empty() {
+                ret += &format!("{title}:\n");
+            }
+            for (name, value) in values {
+                if let Some(path) = sources.get(&name) {
+                    ret += &format!("    {name} = {value}  # {path:?}\n");
+                }
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::ConfigFormat;
+
+    #[test]
+    fn display_merged_sources() {
+        let default = ConfigFormat::Json
+            .parse(crate::config::default_json_configuration())
+            .unwrap();
+        let parse = |content: &str| ConfigFormat::Json.parse(content).unwrap();
+        let chain = vec![
+            (
+                PathBuf::from("project.json"),
+                parse(
+                    r#"{
+                        "defaults": { "--hidden": true, "--tar": "out.tar" },
+                        "opts": [
+                            { "id": "tar", "option": "--tar=s", "value": ["out.tar"] },
+                            { "id": "proto", "option": "-proto=s", "value": ["proto"] }
+                        ]
+                    }"#,
+                ),
+            ),
+            (
+                PathBuf::from("user.json"),
+                parse(
+                    r#"{
+                        "aliases": { "native": ["-c", "-h"] },
+                        "opts": [{ "id": "proto", "option": "-p2=s", "help": "Protobuf", "value": ["pb"] }]
+                    }"#,
+                ),
+            ),
+        ];
+        let display = display(&default, &chain, 1);
+
+        assert!(display.contains(
+            "proto:\n    help = \"Protobuf\"  # \"user.json\"\n    option = \"-proto=s\"  # \"project.json\"\n    value = \"proto\"  # \"project.json\"\n    value = \"pb\"  # \"user.json\"\n"
+        ));
+        assert!(display.contains("    --hidden = true  # \"project.json\"\n"));
+        assert!(display.contains("    native = [\"-c\",\"-h\"]  # \"user.json\"\n"));
+        assert!(!display.contains("--tar = "));
+        assert!(!display.contains("out.tar"));
+    }
+}
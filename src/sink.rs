@@ -0,0 +1,240 @@
+use std::io::BufWriter;
+use std::io::ErrorKind;
+use std::io::Write;
+
+use crate::finder::Matched;
+
+/// Deliver the matched files, such as print them to stdout.
+pub trait Sink: Send {
+    fn send(&mut self, matched: &Matched) -> std::io::Result<()>;
+
+    fn flush(&mut self) -> std::io::Result<()>;
+
+    /// Return true if the reader went away, the matched files are dropped silently after that.
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+/// Collect the matched files.
+impl Sink for Vec<Matched> {
+    fn send(&mut self, matched: &Matched) -> std::io::Result<()> {
+        self.push(matched.clone());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Buffered writer shared by the sinks, it is closed when got a broken pipe error.
+#[derive(Debug)]
+struct Output<W: Write> {
+    writer: BufWriter<W>,
+
+    closed: bool,
+
+    /// Flush after every matched file, otherwise flush when the buffer is full.
+    line: bool,
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            closed: false,
+            line: false,
+        }
+    }
+
+    fn check(&mut self, ret: std::io::Result<()>) -> std::io::Result<()> {
+        match ret {
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(())
+            }
+            ret => ret,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let ret = self.writer.write_all(bytes);
+
+        self.check(ret)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let ret = self.writer.flush();
+
+        self.check(ret)
+    }
+}
+
+macro_rules! impl_output_sink {
+    ($name:ident) => {
+        impl<W: Write + Send> Sink for $name<W> {
+            fn send(&mut self, matched: &Matched) -> std::io::Result<()> {
+                let line = self.format(matched)?;
+
+                self.output.write(line.as_bytes())?;
+                if self.output.line {
+                    self.output.flush()?;
+                }
+                Ok(())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.output.flush()
+            }
+
+            fn is_closed(&self) -> bool {
+                self.output.closed
+            }
+        }
+
+        impl<W: Write> $name<W> {
+            /// Flush the output after every matched file, such as when writing to a terminal.
+            pub fn line_buffered(mut self, line: bool) -> Self {
+                self.output.line = line;
+                self
+            }
+        }
+    };
+}
+
+/// Write one path per line.
+#[derive(Debug)]
+pub struct LineSink<W: Write> {
+    output: Output<W>,
+}
+
+impl<W: Write> LineSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            output: Output::new(writer),
+        }
+    }
+
+    fn format(&self, matched: &Matched) -> std::io::Result<String> {
+        Ok(format!("{}\n", matched.path))
+    }
+}
+
+impl_output_sink!(LineSink);
+
+/// Write the paths separated by `\0`, such as `find -print0`.
+#[derive(Debug)]
+pub struct NulSink<W: Write> {
+    output: Output<W>,
+}
+
+impl<W: Write> NulSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            output: Output::new(writer),
+        }
+    }
+
+    fn format(&self, matched: &Matched) -> std::io::Result<String> {
+        Ok(format!("{}\0", matched.path))
+    }
+}
+
+impl_output_sink!(NulSink);
+
+/// Write one json object per line, with the path, category and entry.
+#[derive(Debug)]
+pub struct JsonLinesSink<W: Write> {
+    output: Output<W>,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            output: Output::new(writer),
+        }
+    }
+
+    fn format(&self, matched: &Matched) -> std::io::Result<String> {
+        Ok(format!("{}\n", serde_json::to_string(matched)?))
+    }
+}
+
+impl_output_sink!(JsonLinesSink);
+
+/// Write the csv with header `path,category,entry`.
+#[derive(Debug)]
+pub struct CsvSink<W: Write> {
+    output: Output<W>,
+}
+
+impl<W: Write> CsvSink<W> {
+    const HEADER: &'static str = "path,category,entry\n";
+
+    /// Create the sink and write the header.
+    pub fn new(writer: W) -> std::io::Result<Self> {
+        let mut output = Output::new(writer);
+
+        output.write(Self::HEADER.as_bytes())?;
+        Ok(Self { output })
+    }
+
+    fn format(&self, matched: &Matched) -> std::io::Result<String> {
+        Ok(format!(
+            "{},{},{}\n",
+            csv_field(&matched.path),
+            csv_field(matched.category.as_deref().unwrap_or_default()),
+            matched.entry
+        ))
+    }
+}
+
+impl_output_sink!(CsvSink);
+
+/// Quote the field if it contains the separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quote_csv_field() {
+        assert_eq!(csv_field("src/a.rs"), "src/a.rs");
+        assert_eq!(csv_field("a,b.rs"), "\"a,b.rs\"");
+        assert_eq!(csv_field("a\"b.rs"), "\"a\"\"b.rs\"");
+        assert_eq!(csv_field("a\nb.rs"), "\"a\nb.rs\"");
+    }
+
+    #[test]
+    fn write_csv() {
+        let mut buffer = vec![];
+        let mut sink = CsvSink::new(&mut buffer).unwrap();
+
+        sink.send(&Matched {
+            path: String::from("a,b.zip!/c.rs"),
+            category: Some(String::from("rust")),
+            entry: true,
+        })
+        .unwrap();
+        sink.flush().unwrap();
+        drop(sink);
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "path,category,entry\n\"a,b.zip!/c.rs\",rust,true\n"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use aopt::HashMap;
+
+use crate::json::JsonComment;
+
+/// Category name used for the file that not belong to any category.
+pub const OTHER_CATEGORY: &str = "other";
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LineCount {
+    pub files: usize,
+
+    pub total: usize,
+
+    pub blank: usize,
+
+    pub comment: usize,
+}
+
+impl LineCount {
+    pub fn code(&self) -> usize {
+        self.total - self.blank - self.comment
+    }
+
+    pub fn merge(&mut self, other: &Self) -> &mut Self {
+        self.files += other.files;
+        self.total += other.total;
+        self.blank += other.blank;
+        self.comment += other.comment;
+        self
+    }
+}
+
+/// Count the lines of `content`, a line is treat as comment if it has no code
+/// outside the line comments and block comments.
+pub fn count_lines(content: &str, comment: Option<&JsonComment>) -> LineCount {
+    let mut count = LineCount {
+        files: 1,
+        ..Default::default()
+    };
+    let mut block_end: Option<&str> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        count.total += 1;
+        if block_end.is_none() && line.is_empty() {
+            count.blank += 1;
+        } else if let Some(comment) = comment {
+            if scan_line(line, comment, &mut block_end) {
+                count.comment += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Return true if the line has no code, the `block_end` is the end delimiter of
+/// block comment left open by previous line and it is updated for next line.
+fn scan_line<'a>(line: &str, comment: &'a JsonComment, block_end: &mut Option<&'a str>) -> bool {
+    let mut rest = line;
+    let mut code = false;
+
+    loop {
+        // the end is searched after the begin, so `"""` can close the block it opened
+        if let Some(end) = *block_end {
+            match rest.find(end) {
+                Some(pos) => {
+                    rest = &rest[pos + end.len()..];
+                    *block_end = None;
+                }
+                None => return !code,
+            }
+        }
+        rest = rest.trim_start();
+        // find the first comment, the line comment has no end
+        let next = comment
+            .line
+            .iter()
+            .map(|beg| (beg, None))
+            .chain(comment.block.iter().map(|(beg, end)| (beg, Some(end))))
+            .filter_map(|(beg, end)| rest.find(beg.as_str()).map(|pos| (pos, beg, end)))
+            .min_by_key(|(pos, _, _)| *pos);
+
+        match next {
+            Some((pos, beg, end)) => {
+                code |= pos > 0;
+                match end {
+                    Some(end) => {
+                        rest = &rest[pos + beg.len()..];
+                        *block_end = Some(end.as_str());
+                    }
+                    None => return !code,
+                }
+            }
+            None => return !code && rest.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    comments: HashMap<String, JsonComment>,
+
+    counts: BTreeMap<String, LineCount>,
+}
+
+impl Stats {
+    pub fn new(comments: HashMap<String, JsonComment>) -> Self {
+        Self {
+            comments,
+            counts: BTreeMap::default(),
+        }
+    }
+
+    pub async fn add_file(&mut self, category: &str, path: &str) -> color_eyre::Result<()> {
+        let content = tokio::fs::read(path).await?;
+        let content = String::from_utf8_lossy(&content);
+        let count = count_lines(&content, self.comments.get(category));
+
+        self.counts
+            .entry(category.to_owned())
+            .or_default()
+            .merge(&count);
+        Ok(())
+    }
+
+    pub fn total(&self) -> LineCount {
+        self.counts
+            .values()
+            .fold(LineCount::default(), |mut acc, count| *acc.merge(count))
+    }
+
+    /// Display the statistics as a table, one row for every category.
+    pub fn display(&self) -> String {
+        let mut ret = format!(
+            "{:<16}{:>10}{:>12}{:>12}{:>12}{:>12}\n",
+            "Category", "Files", "Lines", "Blank", "Comment", "Code"
+        );
+        let total = self.total();
+        let rows = self.counts.iter().map(|(k, v)| (k.as_str(), v));
+
+        for (category, count) in rows.chain(std::iter::once(("Total", &total))) {
+            ret += &format!(
+                "{:<16}{:>10}{:>12}{:>12}{:>12}{:>12}\n",
+                category,
+                count.files,
+                count.total,
+                count.blank,
+                count.comment,
+                count.code()
+            );
+        }
+        ret
+    }
+
+    /// Display the statistics as a json object, with a `total` field.
+    pub fn display_json(&self) -> serde_json::Result<String> {
+        let row = |count: &LineCount| {
+            serde_json::json!({
+                "files": count.files,
+                "lines": count.total,
+                "blank": count.blank,
+                "comment": count.comment,
+                "code": count.code(),
+            })
+        };
+        let categories: serde_json::Map<String, serde_json::Value> = self
+            .counts
+            .iter()
+            .map(|(k, v)| (k.clone(), row(v)))
+            .collect();
+
+        serde_json::to_string_pretty(&serde_json::json!({
+            "categories": categories,
+            "total": row(&self.total()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_block_comment() {
+        let comment = JsonComment {
+            line: vec![String::from("//")],
+            block: vec![(String::from("/*"), String::from("*/"))],
+        };
+        let content = "// line\nfn a() {}\n\n/* block\n  inside\n*/\nlet x = 1; /* open\nclose */\n/* one */ let y = 2;\n";
+        let count = count_lines(content, Some(&comment));
+
+        assert_eq!(count.total, 9);
+        assert_eq!(count.blank, 1);
+        assert_eq!(count.comment, 5);
+        assert_eq!(count.code(), 3);
+    }
+
+    #[test]
+    fn count_without_comment() {
+        let count = count_lines("// a\n\nb\n", None);
+
+        assert_eq!((count.total, count.blank, count.comment), (3, 1, 0));
+    }
+
+    #[test]
+    fn count_symmetric_block_comment() {
+        let comment = JsonComment {
+            line: vec![String::from("#")],
+            block: vec![(String::from("\"\"\""), String::from("\"\"\""))],
+        };
+        let content = "\"\"\"doc\"\"\"\ndef a():\n    \"\"\"\n    doc\n    \"\"\"\n    return 1  # \"\"\" not a block\nb = 2\n";
+        let count = count_lines(content, Some(&comment));
+
+        assert_eq!(count.total, 7);
+        assert_eq!(count.comment, 4);
+        assert_eq!(count.code(), 3);
+    }
+}
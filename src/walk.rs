@@ -0,0 +1,133 @@
+use tokio::sync::mpsc::channel;
+use tokio::sync::mpsc::Receiver;
+use tokio_stream::StreamExt;
+
+use crate::builder::FinderBuilder;
+use crate::cancel::CancelToken;
+use crate::error::FindError;
+use crate::finder::Matched;
+
+type Item = Result<Matched, FindError>;
+
+/// The blocking iterator of matched files returned by [`FinderBuilder::walk`](crate::FinderBuilder::walk).
+///
+/// The searching is stopped when it is dropped or the token is cancelled.
+pub struct Walk {
+    rx: Receiver<Item>,
+
+    token: CancelToken,
+}
+
+impl Walk {
+    /// Run the finder of builder on a runtime of a new thread, the matched files are forwarded to the iterator.
+    pub(crate) fn spawn(builder: FinderBuilder, threads: usize, capacity: usize) -> Self {
+        let (tx, rx) = channel(capacity);
+        let token = builder.cancel_token().clone();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(threads.max(1))
+            .enable_all()
+            .build();
+
+        match runtime {
+            Ok(runtime) => {
+                let token = token.clone();
+
+                std::thread::spawn(move || {
+                    runtime.block_on(async move {
+                        let mut matches = builder.find();
+
+                        while let Some(matched) = matches.next().await {
+                            // the iterator is dropped
+                            if tx.send(matched).await.is_err() {
+                                token.cancel();
+                                break;
+                            }
+                        }
+                    })
+                });
+            }
+            Err(e) => {
+                let root = builder.roots().first().cloned().unwrap_or_default();
+                let _ = tx.try_send(Err(FindError::io(root, e)));
+            }
+        }
+        Self { rx, token }
+    }
+
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.token.is_cancelled() {
+            None
+        } else {
+            self.rx.blocking_recv()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("findsource-{}-{}", name, std::process::id()));
+
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("a.rs"), "").unwrap();
+        std::fs::write(root.join("a.c"), "").unwrap();
+        std::fs::write(root.join("sub").join("b.rs"), "").unwrap();
+        std::fs::write(root.join(".hidden").join("c.rs"), "").unwrap();
+        root
+    }
+
+    #[test]
+    fn walk_matched_files() {
+        let root = temp_root("walk");
+        let mut builder = FinderBuilder::new();
+
+        builder.add_root(&root).add_category("rust", ["rs"]);
+        let mut paths: Vec<_> = builder
+            .walk()
+            .map(|matched| PathBuf::from(matched.unwrap().path))
+            .collect();
+
+        paths.sort();
+        assert_eq!(paths, [root.join("a.rs"), root.join("sub").join("b.rs")]);
+        builder.set_hidden(true);
+        assert_eq!(builder.walk().count(), 3);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn walk_stop_when_dropped() {
+        let root = temp_root("walk-drop");
+        let mut builder = FinderBuilder::new();
+
+        builder
+            .add_root(&root)
+            .add_extension("rs")
+            .set_invert(true)
+            .set_capacity(1);
+        let mut walk = builder.walk();
+
+        assert!(walk.next().is_some());
+        drop(walk);
+        for _ in 0..100 {
+            if builder.cancel_token().is_cancelled() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(builder.cancel_token().is_cancelled());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
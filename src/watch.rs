@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::event::ModifyKind;
+use notify::event::RenameMode;
+use notify::Event;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::note;
+use crate::say;
+use findsource::finder::Filter;
+use findsource::matcher::Candidate;
+
+/// A search root, with the forms used to translate the path of events.
+#[derive(Debug, Clone)]
+struct Root {
+    /// The path given by user.
+    path: PathBuf,
+
+    /// The absolute path, notify report the path based on it.
+    absolute: PathBuf,
+
+    /// The canonical path, used when `--full` is set.
+    canonical: PathBuf,
+}
+
+/// The events of same path in the duration are handled once, such as the modifies of one write.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The changes of paths collected from events, with the `rescan` and `modify` flags.
+type Changes = BTreeMap<PathBuf, (bool, bool)>;
+
+/// Keep watching the search roots, and report the file entering (`+`),
+/// changing (`~`) or leaving (`-`) the matched set.
+#[derive(Debug)]
+pub struct Watch {
+    roots: Vec<Root>,
+
+    filter: Arc<Filter>,
+
+    full: bool,
+
+    recursive: bool,
+
+    debug: bool,
+
+    matched: HashSet<String>,
+
+    /// The watcher is started before the searching, the events are kept in the channel.
+    _watcher: RecommendedWatcher,
+
+    events: UnboundedReceiver<notify::Result<Event>>,
+}
+
+impl Watch {
+    pub fn new(
+        roots: &[PathBuf],
+        filter: Arc<Filter>,
+        full: bool,
+        recursive: bool,
+        debug: bool,
+    ) -> color_eyre::Result<Self> {
+        let cwd = std::env::current_dir()?;
+        let roots = roots
+            .iter()
+            .map(|path| {
+                Ok(Root {
+                    path: path.clone(),
+                    absolute: cwd.join(path),
+                    canonical: dunce::canonicalize(path)?,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let (tx, events) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+
+        for root in roots.iter() {
+            watcher.watch(&root.absolute, mode)?;
+        }
+        Ok(Self {
+            roots,
+            filter,
+            full,
+            recursive,
+            debug,
+            matched: HashSet::default(),
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    pub fn insert(&mut self, path: String) -> &mut Self {
+        self.matched.insert(path);
+        self
+    }
+
+    pub async fn run(mut self) -> color_eyre::Result<()> {
+        if self.debug {
+            note!("INFO: ... watching the search path");
+        }
+        while let Some(event) = self.events.recv().await {
+            let mut changes = Changes::default();
+            let deadline = tokio::time::sleep(DEBOUNCE);
+
+            Self::collect(event, &mut changes).await;
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    event = self.events.recv() => match event {
+                        Some(event) => Self::collect(event, &mut changes).await,
+                        None => break,
+                    },
+                }
+            }
+            for (path, (rescan, modify)) in changes {
+                self.process_path(path, rescan, modify).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the paths of event into the changes.
+    async fn collect(event: notify::Result<Event>, changes: &mut Changes) {
+        match event {
+            Ok(event) => {
+                let (rescan, modify) = match event.kind {
+                    // the access is also reported for our own `read_dir`
+                    EventKind::Access(_) | EventKind::Other => return,
+                    EventKind::Create(_) => (true, false),
+                    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => (false, false),
+                    EventKind::Modify(ModifyKind::Name(_)) => (true, false),
+                    EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => (false, true),
+                    _ => (false, false),
+                };
+
+                for path in event.paths {
+                    let change = changes.entry(path).or_default();
+
+                    change.0 |= rescan;
+                    change.1 |= modify;
+                }
+            }
+            Err(e) => {
+                note!("ERROR: Can not watch the search path: {:?}", e);
+            }
+        }
+    }
+
+    /// Update the matched set with the changed path,
+    /// the files in directory are checked if the directory is created or moved in (`rescan`).
+    async fn process_path(
+        &mut self,
+        path: PathBuf,
+        rescan: bool,
+        modify: bool,
+    ) -> color_eyre::Result<()> {
+        let mut pendings = vec![path];
+
+        while let Some(path) = pendings.pop() {
+            let Some((display, rel)) = self.locate(&path) else {
+                continue;
+            };
+
+            if path.is_dir() {
+                if rescan && self.recursive && !self.is_hidden(&rel) {
+                    // the directory may be removed before reading it
+                    if let Err(e) = Self::read_dir(&path, &mut pendings).await {
+                        note!("ERROR: Can not read directory `{}`: {:?}", display, e);
+                    }
+                }
+            } else if self.is_matched(&path, &rel) {
+                if self.matched.insert(display.clone()) {
+                    say!("+ {}", display);
+                } else if modify {
+                    say!("~ {}", display);
+                }
+            } else if self.matched.remove(&display) {
+                say!("- {}", display);
+            } else if !path.exists() {
+                // a directory removed or moved out
+                let prefix = format!("{}{}", display, std::path::MAIN_SEPARATOR);
+                let mut removed: Vec<_> = self
+                    .matched
+                    .iter()
+                    .filter(|v| v.starts_with(&prefix))
+                    .cloned()
+                    .collect();
+
+                removed.sort();
+                for path in removed {
+                    self.matched.remove(&path);
+                    say!("- {}", path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_dir(path: &Path, pendings: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        let mut entries = tokio::fs::read_dir(path).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            pendings.push(entry.path());
+        }
+        Ok(())
+    }
+
+    /// Get the display path and the path relative to search root.
+    fn locate(&self, path: &Path) -> Option<(String, PathBuf)> {
+        self.roots.iter().find_map(|root| {
+            let rel = path.strip_prefix(&root.absolute).ok()?;
+            let base = if self.full {
+                &root.canonical
+            } else {
+                &root.path
+            };
+            let display = if rel.as_os_str().is_empty() {
+                base.clone()
+            } else {
+                base.join(rel)
+            };
+
+            display
+                .to_str()
+                .map(|display| (display.to_owned(), rel.to_path_buf()))
+        })
+    }
+
+    fn is_hidden(&self, rel: &Path) -> bool {
+        !self.filter.hidden()
+            && rel
+                .components()
+                .filter_map(|v| v.as_os_str().to_str())
+                .any(|v| v.starts_with('.'))
+    }
+
+    fn is_matched(&self, path: &Path, rel: &Path) -> bool {
+        if !path.is_file() || self.is_hidden(rel) {
+            return false;
+        }
+        if let Some(file_name) = path.file_name().and_then(|v| v.to_str()) {
+            self.filter
+                .category(&Candidate::new(path, file_name))
+                .is_some()
+                || self.filter.invert()
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use notify::event::AccessKind;
+    use notify::event::CreateKind;
+    use notify::event::DataChange;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn collect_changes_by_path() {
+        let event = |kind, path: &str| Ok(Event::new(kind).add_path(PathBuf::from(path)));
+        let mut changes = Changes::default();
+
+        Watch::collect(
+            event(EventKind::Create(CreateKind::File), "a.rs"),
+            &mut changes,
+        )
+        .await;
+        for _ in 0..2 {
+            let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+
+            Watch::collect(event(kind, "a.rs"), &mut changes).await;
+            Watch::collect(event(kind, "b.rs"), &mut changes).await;
+        }
+        Watch::collect(
+            event(EventKind::Access(AccessKind::Any), "c.rs"),
+            &mut changes,
+        )
+        .await;
+
+        assert_eq!(
+            changes.into_iter().collect::<Vec<_>>(),
+            [
+                (PathBuf::from("a.rs"), (true, true)),
+                (PathBuf::from("b.rs"), (false, true))
+            ]
+        );
+    }
+}